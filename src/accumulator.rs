@@ -0,0 +1,87 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use crate::birthdeath::BirthDeath;
+use crate::error::Error;
+use crate::persistencelandscape;
+use crate::piecewiselinear;
+use crate::rpls;
+use crate::tolerance::Tolerance;
+
+/// Running exact sum of landscapes used to compute a mean landscape without keeping every
+/// landscape in memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct LandscapeAccumulator {
+    sum: Vec<Vec<(f64,f64)>>,
+    count: usize,
+}
+
+impl LandscapeAccumulator {
+    #[must_use]
+    pub fn new(k: usize) -> Self {
+        Self {
+            sum: persistencelandscape::empty_landscape(k),
+            count: 0,
+        }
+    }
+
+    /// Fold a landscape into the running sum
+    pub fn add(&mut self, landscape: &[Vec<(f64,f64)>]) {
+        add_levels(&mut self.sum, landscape);
+        self.count += 1;
+    }
+
+    /// Compute the landscape of `bd_pairs` with `rpls::pairs_to_landscape` and fold it in
+    ///
+    /// # Errors
+    ///
+    /// Will return 'Err' if failed to compute persistencelandscape from `bd_pairs`
+    pub fn add_pairs(&mut self, bd_pairs: Vec<BirthDeath>, debug: bool, disable_filter: bool, tolerance: Tolerance) -> Result<(), Error> {
+        let landscape = rpls::pairs_to_landscape(bd_pairs, self.sum.len(), debug, disable_filter, tolerance)?;
+        self.add(&landscape);
+        Ok(())
+    }
+
+    /// Combine with an accumulator built over a different set of landscapes
+    pub fn merge(&mut self, other: &Self) {
+        add_levels(&mut self.sum, &other.sum);
+        self.count += other.count;
+    }
+
+    #[must_use]
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+
+    #[must_use]
+    pub fn sum(&self) -> &[Vec<(f64,f64)>] {
+        &self.sum
+    }
+
+    /// Mean of every landscape folded in so far, all levels are empty if nothing was added
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> Vec<Vec<(f64,f64)>> {
+        if self.count == 0 {
+            return persistencelandscape::empty_landscape(self.sum.len());
+        }
+        let c = 1.0 / self.count as f64;
+        self.sum
+            .iter()
+            .map(|level| piecewiselinear::scale(level, c))
+            .collect()
+    }
+}
+
+fn add_levels(sum: &mut Vec<Vec<(f64,f64)>>, landscape: &[Vec<(f64,f64)>]) {
+    if sum.len() < landscape.len() {
+        sum.resize(landscape.len(), Vec::new());
+    }
+    for (total, level) in sum.iter_mut().zip(landscape) {
+        *total = piecewiselinear::add(total, level);
+    }
+}
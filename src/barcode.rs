@@ -5,7 +5,8 @@
      clippy::cargo,
  )]
 
-use crate::birthdeath::BirthDeath;
+use crate::birthdeath::{self, BirthDeath};
+use crate::error::Error;
 use std::collections::{BinaryHeap, VecDeque};
 
 #[derive(Debug, Clone)]
@@ -100,63 +101,60 @@ const fn node_to_birthdeath(n: &Node) -> BirthDeath {
     }
 }
 
-/// # Panics
+/// The pairs that can reach the first `k` levels of the landscape
 ///
-/// Will panic is structure gets corrupted
-#[must_use]
-pub fn filter(bd_pairs: Vec<BirthDeath>, k: usize) -> Vec<BirthDeath> {
+/// Pairs enter a top of `k` when they are born and there is room, when one in the top dies the
+/// oldest waiting pair alive takes its place. While a pair waits the top stays full of pairs born
+/// no later than it, so a pair that dies waiting has `k` of them still alive at its death. Their
+/// tents contain its tent and it never reaches the first `k` levels. Every pair that enters the
+/// top is kept and the rest dropped.
+///
+/// # Errors
+///
+/// Will return 'Err' if a pair is invalid
+pub fn filter(bd_pairs: Vec<BirthDeath>, k: usize) -> Result<Vec<BirthDeath>, Error> {
+    birthdeath::check_pairs(&bd_pairs)?;
     let mut nodes = generate_events(bd_pairs);
     let mut event_stack = BinaryHeap::from(nodes.clone());
-    let sweep_status: &mut VecDeque<usize> = &mut VecDeque::new();
+    // Alive pairs outside the top k, oldest first. Dead ones are removed lazily
+    let waiting: &mut VecDeque<usize> = &mut VecDeque::new();
     let mut filtered_output: Vec<BirthDeath> = Vec::new();
     let mut in_top = 0;
-    let mut waiting = 0;
 
     while let Some(mut event) = event_stack.pop(){
         match get_value(&event).event_type {
             EventType::Birth => {
-                // Check if in top-k and handle
+                // The youngest pair alive, only in the top k if there is room
                 if in_top < k {
                     in_top += 1;
                     filtered_output.push(node_to_birthdeath(&event));
-                    // Mark so we know when it dies
                     nodes[event.id].in_top_k = true;
+                } else {
+                    waiting.push_back(event.id);
                 }
                 // Normal processing of value
                 nodes[event.id].alive = true;
                 event.alive = true;
-                sweep_status.push_back(event.id);
                 // Add the event back into to register its death_event
                 event_stack.push(event);
             }
             EventType::Death => {
-                // mark as dead
                 nodes[event.id].is_dead = true;
-                // if was in top-k promote next canidate
-                // lazy add
-                if nodes[event.id].in_top_k {
-                    waiting += 1;
+                if !nodes[event.id].in_top_k {
+                    continue;
                 }
-                // check if next element should be in top k
-                if waiting > 0 {
-                    let mut front_index = sweep_status.get(0);
-                    while front_index.is_some() && nodes[*front_index.unwrap()].is_dead {
-                        sweep_status.pop_front();
-                        front_index = sweep_status.get(0);
-                    }
-                    // If queue has no canidate events move on
-                    if front_index.is_none() {
-                        continue;
-                    }
-                    // Found a canidate, add it to top k
-                    let front = *front_index.unwrap();
-                    if !nodes[front].in_top_k {
-                        nodes[front].in_top_k = true;
-                        filtered_output.push(node_to_birthdeath(&nodes[front]));
-                    }
+                // A place in the top k opened up, promote the oldest waiting pair still alive
+                in_top -= 1;
+                while waiting.front().is_some_and(|&front| nodes[front].is_dead) {
+                    waiting.pop_front();
+                }
+                if let Some(front) = waiting.pop_front() {
+                    in_top += 1;
+                    nodes[front].in_top_k = true;
+                    filtered_output.push(node_to_birthdeath(&nodes[front]));
                 }
             }
         }
     }
-    filtered_output
+    Ok(filtered_output)
 }
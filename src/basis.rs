@@ -0,0 +1,241 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::f64::consts::PI;
+
+use crate::error::Error;
+use crate::piecewiselinear;
+
+/// Functional bases a landscape level can be projected onto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Basis {
+    /// Orthonormal Legendre polynomials of degree 0 to `order`
+    Legendre,
+    /// Clamped B-splines of `degree` on `order` uniform intervals, `order + degree` functions
+    BSpline { degree: usize },
+    /// Orthonormal constant, cosine and sine modes up to frequency `order`, in that order
+    Fourier,
+}
+
+impl Basis {
+    /// Number of coefficients of a projection truncated at `order`
+    #[must_use]
+    pub const fn len(self, order: usize) -> usize {
+        match self {
+            Self::Legendre => order + 1,
+            Self::BSpline { degree } => order + degree,
+            Self::Fourier => 2 * order + 1,
+        }
+    }
+}
+
+// Pieces of `level` on [a, b] split at every vertex and every x of `extra`. The level is linear on
+// each piece, which is returned as (x0, x1, y0, y1).
+fn pieces(level: &[(f64,f64)], (a, b): (f64, f64), extra: &[f64]) -> Vec<(f64, f64, f64, f64)> {
+    let mut xs: Vec<f64> = level
+        .iter()
+        .map(|p| p.0)
+        .chain(extra.iter().copied())
+        .chain([a, b])
+        .filter(|&x| x >= a && x <= b)
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    xs.windows(2)
+        .map(|w| (w[0], w[1], piecewiselinear::evaluate(level, w[0]), piecewiselinear::evaluate(level, w[1])))
+        .filter(|&(x0, x1, y0, y1)| x1 > x0 && (y0 != 0.0 || y1 != 0.0))
+        .collect()
+}
+
+// P_0(u) to P_n(u) by the three term recurrence
+#[allow(clippy::cast_precision_loss)]
+fn legendre_values(u: f64, n: usize) -> Vec<f64> {
+    let mut values = vec![1.0, u];
+    for i in 1..n {
+        let i_f = i as f64;
+        let next = 2.0f64.mul_add(i_f, 1.0).mul_add(u * values[i], -i_f * values[i - 1]) / (i_f + 1.0);
+        values.push(next);
+    }
+    values.truncate(n + 1);
+    values
+}
+
+// Antiderivatives of P_n(u) and u P_n(u) for n up to `order` using
+// (2n + 1) P_n = P'_{n+1} - P'_{n-1} and (2n + 1) u P_n = (n + 1) P_{n+1} + n P_{n-1}
+#[allow(clippy::cast_precision_loss)]
+fn legendre_antiderivatives(u: f64, order: usize) -> (Vec<f64>, Vec<f64>) {
+    let p = legendre_values(u, order + 2);
+    let integral: Vec<f64> = (0..=order + 1)
+        .map(|n| if n == 0 { u } else { (p[n + 1] - p[n - 1]) / (2 * n + 1) as f64 })
+        .collect();
+    let moment = (0..=order)
+        .map(|n| {
+            let below = if n == 0 { 0.0 } else { n as f64 * integral[n - 1] };
+            (n as f64 + 1.0).mul_add(integral[n + 1], below) / (2 * n + 1) as f64
+        })
+        .collect();
+    (integral, moment)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn legendre(level: &[(f64,f64)], (a, b): (f64, f64), order: usize) -> Vec<f64> {
+    let to_u = |x: f64| 2.0f64.mul_add(x, -a - b) / (b - a);
+    let mut coefficients = vec![0.0; order + 1];
+    for (x0, x1, y0, y1) in pieces(level, (a, b), &[]) {
+        let (u0, u1) = (to_u(x0), to_u(x1));
+        // The level is c0 + c1 u on the piece
+        let c1 = (y1 - y0) / (u1 - u0);
+        let c0 = (-c1).mul_add(u0, y0);
+        let (i0, m0) = legendre_antiderivatives(u0, order);
+        let (i1, m1) = legendre_antiderivatives(u1, order);
+        for (n, c) in coefficients.iter_mut().enumerate() {
+            *c += c0.mul_add(i1[n] - i0[n], c1 * (m1[n] - m0[n]));
+        }
+    }
+    // dx = (b - a) / 2 du and the norm of P_n on [a, b] is sqrt((b - a) / (2n + 1))
+    for (n, c) in coefficients.iter_mut().enumerate() {
+        *c *= (b - a) / 2.0 * ((2 * n + 1) as f64 / (b - a)).sqrt();
+    }
+    coefficients
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn fourier(level: &[(f64,f64)], (a, b): (f64, f64), order: usize) -> Vec<f64> {
+    let length = b - a;
+    let mut coefficients = vec![0.0; 2 * order + 1];
+    for (x0, x1, y0, y1) in pieces(level, (a, b), &[]) {
+        let slope = (y1 - y0) / (x1 - x0);
+        coefficients[0] += (x1 - x0) * (y0 + y1) / 2.0;
+        for k in 1..=order {
+            let w = 2.0 * PI * k as f64 / length;
+            // Antiderivatives of f(x) cos(w (x - a)) and f(x) sin(w (x - a)) for linear f
+            let cos_part = |x: f64, y: f64| {
+                let (sin, cos) = (w * (x - a)).sin_cos();
+                (y * sin).mul_add(w, slope * cos) / (w * w)
+            };
+            let sin_part = |x: f64, y: f64| {
+                let (sin, cos) = (w * (x - a)).sin_cos();
+                (-y * cos).mul_add(w, slope * sin) / (w * w)
+            };
+            coefficients[2 * k - 1] += cos_part(x1, y1) - cos_part(x0, y0);
+            coefficients[2 * k] += sin_part(x1, y1) - sin_part(x0, y0);
+        }
+    }
+    coefficients[0] /= length.sqrt();
+    for c in &mut coefficients[1..] {
+        *c *= (2.0 / length).sqrt();
+    }
+    coefficients
+}
+
+// Multiply a polynomial in u by (c0 + c1 u)
+fn multiply_linear(poly: &[f64], c0: f64, c1: f64) -> Vec<f64> {
+    let mut out = vec![0.0; poly.len() + 1];
+    for (i, &p) in poly.iter().enumerate() {
+        out[i] = p.mul_add(c0, out[i]);
+        out[i + 1] = p.mul_add(c1, out[i + 1]);
+    }
+    out
+}
+
+// B_{s-degree} to B_s on the knot span [t_s, t_{s+1}) as polynomials in u = x - t_s, from the
+// Cox-de Boor recursion
+fn span_polynomials(knots: &[f64], s: usize, degree: usize) -> Vec<Vec<f64>> {
+    let ts = knots[s];
+    // polys[i] holds B_{s - p + i, p}
+    let mut polys = vec![vec![1.0]];
+    for p in 1..=degree {
+        let mut next = Vec::with_capacity(p + 1);
+        for i in 0..=p {
+            let j = s + i - p;
+            let mut poly = vec![0.0; p + 1];
+            // (x - t_j) / (t_{j+p} - t_j) B_{j,p-1}
+            if i > 0 {
+                let width = knots[j + p] - knots[j];
+                if width > 0.0 {
+                    let term = multiply_linear(&polys[i - 1], (ts - knots[j]) / width, 1.0 / width);
+                    poly.iter_mut().zip(&term).for_each(|(a, b)| *a += b);
+                }
+            }
+            // (t_{j+p+1} - x) / (t_{j+p+1} - t_{j+1}) B_{j+1,p-1}
+            if i < p {
+                let width = knots[j + p + 1] - knots[j + 1];
+                if width > 0.0 {
+                    let term = multiply_linear(&polys[i], (knots[j + p + 1] - ts) / width, -1.0 / width);
+                    poly.iter_mut().zip(&term).for_each(|(a, b)| *a += b);
+                }
+            }
+            next.push(poly);
+        }
+        polys = next;
+    }
+    polys
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn bspline(level: &[(f64,f64)], (a, b): (f64, f64), order: usize, degree: usize) -> Vec<f64> {
+    let knots: Vec<f64> = std::iter::repeat_n(a, degree)
+        .chain((0..=order).map(|i| if i == order { b } else { (b - a).mul_add(i as f64 / order as f64, a) }))
+        .chain(std::iter::repeat_n(b, degree))
+        .collect();
+    let mut coefficients = vec![0.0; order + degree];
+    for (x0, x1, y0, y1) in pieces(level, (a, b), &knots) {
+        // Span holding the piece, the last one is closed on the right
+        let s = (degree..degree + order)
+            .find(|&s| x0 >= knots[s] && x1 <= knots[s + 1])
+            .unwrap_or(degree + order - 1);
+        let ts = knots[s];
+        let (u0, u1) = (x0 - ts, x1 - ts);
+        let c1 = (y1 - y0) / (x1 - x0);
+        let c0 = (-c1).mul_add(u0, y0);
+        for (i, poly) in span_polynomials(&knots, s, degree).iter().enumerate() {
+            let product = multiply_linear(poly, c0, c1);
+            let (mut p0, mut p1, mut integral) = (u0, u1, 0.0);
+            for (k, q) in product.iter().enumerate() {
+                integral += q * (p1 - p0) / (k + 1) as f64;
+                p0 *= u0;
+                p1 *= u1;
+            }
+            coefficients[s + i - degree] += integral;
+        }
+    }
+    coefficients
+}
+
+/// Exact inner products of a landscape level with the first `basis.len(order)` functions of
+/// `basis` on `interval`
+///
+/// The level is linear between its vertices so every integral is evaluated in closed form.
+///
+/// # Errors
+///
+/// Will return 'Err' if `interval` is empty or `order` is zero for B-splines
+pub fn project_level(level: &[(f64,f64)], basis: Basis, interval: (f64, f64), order: usize) -> Result<Vec<f64>, Error> {
+    if interval.0.is_nan() || interval.1.is_nan() || interval.1 <= interval.0 {
+        return Err(Error::InvalidArgument("Projection interval must not be empty".to_string()));
+    }
+    match basis {
+        Basis::Legendre => Ok(legendre(level, interval, order)),
+        Basis::BSpline { .. } if order == 0 => {
+            Err(Error::InvalidArgument("B-spline basis needs at least one interval".to_string()))
+        }
+        Basis::BSpline { degree } => Ok(bspline(level, interval, order, degree)),
+        Basis::Fourier => Ok(fourier(level, interval, order)),
+    }
+}
+
+/// Coefficients of every level of `landscape`, see `project_level`
+///
+/// # Errors
+///
+/// Will return 'Err' if `interval` is empty or `order` is zero for B-splines
+pub fn project(landscape: &[Vec<(f64,f64)>], basis: Basis, interval: (f64, f64), order: usize) -> Result<Vec<Vec<f64>>, Error> {
+    landscape
+        .iter()
+        .map(|level| project_level(level, basis, interval, order))
+        .collect()
+}
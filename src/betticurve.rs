@@ -0,0 +1,61 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use float_ord::FloatOrd;
+
+use crate::birthdeath::BirthDeath;
+
+// Sweep over births (+sign) and deaths (-sign) in increasing order and record the running total
+// after each distinct x. A feature is alive on [birth, death), pairs that never die are counted
+// from their birth onward.
+fn sweep(events: &mut [(f64, i64)]) -> Vec<(f64, i64)> {
+    events.sort_by_key(|(x, _)| FloatOrd(*x));
+    let mut steps: Vec<(f64, i64)> = Vec::new();
+    let mut total = 0;
+    for (i, (x, change)) in events.iter().enumerate() {
+        total += change;
+        let last_at_x = events.get(i + 1).is_none_or(|next| FloatOrd(next.0) != FloatOrd(*x));
+        let changed = steps.last().map_or(total != 0, |last| last.1 != total);
+        if last_at_x && changed {
+            steps.push((*x, total));
+        }
+    }
+    steps
+}
+
+fn signed_events(bd_pairs: &[BirthDeath], sign: i64) -> impl Iterator<Item = (f64, i64)> + '_ {
+    bd_pairs
+        .iter()
+        .filter(|bd| !bd.birth.is_nan() && !bd.death.is_nan() && bd.birth.is_finite())
+        .flat_map(move |bd| {
+            let death = bd.death.is_finite().then_some((bd.death, -sign));
+            std::iter::once((bd.birth, sign)).chain(death)
+        })
+}
+
+/// Betti curve of a single diagram as a step function, see `stepfunction` for evaluating and
+/// sampling it
+#[must_use]
+pub fn betti_curve(bd_pairs: &[BirthDeath]) -> Vec<(f64, usize)> {
+    let mut events: Vec<(f64, i64)> = signed_events(bd_pairs, 1).collect();
+    sweep(&mut events)
+        .into_iter()
+        .map(|(x, count)| (x, usize::try_from(count).unwrap_or(0)))
+        .collect()
+}
+
+/// Euler characteristic curve, the alternating sum of the Betti curves of `diagrams` where
+/// `diagrams[d]` holds the pairs of dimension d
+#[must_use]
+pub fn euler_characteristic_curve(diagrams: &[Vec<BirthDeath>]) -> Vec<(f64, i64)> {
+    let mut events: Vec<(f64, i64)> = diagrams
+        .iter()
+        .enumerate()
+        .flat_map(|(dim, bd_pairs)| signed_events(bd_pairs, if dim % 2 == 0 { 1 } else { -1 }))
+        .collect();
+    sweep(&mut events)
+}
@@ -7,21 +7,81 @@
 
 use std::str::FromStr;
 
+use crate::error::Error;
+
 #[derive(Debug)]
 pub struct BirthDeath {
     pub birth: f64,
     pub death: f64,
 }
 
+impl BirthDeath {
+    #[must_use]
+    pub fn persistence(&self) -> f64 {
+        self.death - self.birth
+    }
+
+    // Parse line `line` of a diagram file, the number is only used in the error
+    fn parse_line(s: &str, line: usize) -> Result<Self, Error> {
+        let parse_error = || Error::Parse { line, content: s.to_string() };
+        let (b, d) = s.trim().split_once(' ').ok_or_else(parse_error)?;
+
+        Ok(Self {
+            birth: b.trim().parse().map_err(|_| parse_error())?,
+            death: d.trim().parse().map_err(|_| parse_error())?,
+        })
+    }
+}
+
 impl FromStr for BirthDeath {
-    type Err = std::string::ParseError;
+    type Err = Error;
 
+    /// Parse a space separated pair, errors report it as line 1
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (b, d) = s.split_once(' ').unwrap();
-
-        return Ok(Self {
-            birth: b.trim().parse().unwrap(),
-            death: d.trim().parse().unwrap(),
-        });
+        Self::parse_line(s, 1)
     }
 }
+
+// Parse every line that passes `keep`, numbering lines from 1 for errors
+fn read_pairs(path: &str, keep: fn(&str) -> bool) -> Result<Vec<BirthDeath>, Error> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, s)| keep(s))
+        .map(|(i, s)| BirthDeath::parse_line(s, i + 1))
+        .collect()
+}
+
+/// Read birth death pairs from a file with one space separated pair per line, skipping empty
+/// lines and pairs that never die
+///
+/// # Errors
+///
+/// Will return 'Err' if `path` can not be read or a line is not a pair of numbers
+pub fn read_file(path: &str) -> Result<Vec<BirthDeath>, Error> {
+    read_pairs(path, |s| !s.contains("inf") && !s.trim().is_empty())
+}
+
+/// Read birth death pairs like `read_file` but keep pairs that never die
+///
+/// # Errors
+///
+/// Will return 'Err' if `path` can not be read or a line is not a pair of numbers
+pub fn read_file_with_infinite(path: &str) -> Result<Vec<BirthDeath>, Error> {
+    read_pairs(path, |s| !s.trim().is_empty())
+}
+
+// Pairs with a NaN coordinate or that die before they are born can not be swept
+pub(crate) fn check_pairs(bd_pairs: &[BirthDeath]) -> Result<(), Error> {
+    bd_pairs
+        .iter()
+        .enumerate()
+        .find(|(_, bd)| bd.birth.is_nan() || bd.death.is_nan() || bd.death < bd.birth)
+        .map_or(Ok(()), |(index, bd)| {
+            Err(Error::InvalidPair {
+                index,
+                birth: bd.birth,
+                death: bd.death,
+            })
+        })
+}
@@ -0,0 +1,122 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::accumulator::LandscapeAccumulator;
+use crate::birthdeath::BirthDeath;
+use crate::error::Error;
+use crate::piecewiselinear;
+use crate::rpls;
+use crate::tolerance::Tolerance;
+
+/// Uniform confidence band around a mean landscape. `lower` and `upper` are the mean shifted by
+/// `half_width` at each of its vertices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfidenceBand {
+    pub mean: Vec<Vec<(f64,f64)>>,
+    pub lower: Vec<Vec<(f64,f64)>>,
+    pub upper: Vec<Vec<(f64,f64)>>,
+    pub half_width: f64,
+    pub level: f64,
+}
+
+impl ConfidenceBand {
+    /// Mean, lower and upper curve of every level in the order `plot::landscape` draws them
+    #[must_use]
+    pub fn to_plot(&self) -> Vec<Vec<(f64,f64)>> {
+        self.mean
+            .iter()
+            .zip(&self.lower)
+            .zip(&self.upper)
+            .flat_map(|((m, l), u)| [m.clone(), l.clone(), u.clone()])
+            .collect()
+    }
+}
+
+fn mean_landscape(landscapes: &[Vec<Vec<(f64,f64)>>], k: usize) -> Vec<Vec<(f64,f64)>> {
+    let mut acc = LandscapeAccumulator::new(k);
+    landscapes.iter().for_each(|l| acc.add(l));
+    acc.mean()
+}
+
+fn shift(landscape: &[Vec<(f64,f64)>], c: f64) -> Vec<Vec<(f64,f64)>> {
+    landscape
+        .iter()
+        .map(|level| level.iter().map(|&(x, y)| (x, y + c)).collect())
+        .collect()
+}
+
+/// Bootstrap a uniform band for the mean landscape
+///
+/// Diagrams are resampled with replacement and the `level` quantile of the sup-norm deviation of
+/// the resampled mean from the mean is used as the half width. Each resample is drawn from its own
+/// generator seeded from `seed`.
+///
+/// # Errors
+///
+/// Will return 'Err' if `landscapes` is empty, `resamples` is zero or `level` is not in (0, 1)
+pub fn bootstrap_band(landscapes: &[Vec<Vec<(f64,f64)>>], level: f64, resamples: usize, seed: u64) -> Result<ConfidenceBand, Error> {
+    if landscapes.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if resamples == 0 {
+        return Err(Error::InvalidArgument("Bootstrap needs at least one resample".to_string()));
+    }
+    if level.is_nan() || level <= 0.0 || level >= 1.0 {
+        return Err(Error::InvalidArgument(format!("Confidence level {level} is not in (0, 1)")));
+    }
+    let k = landscapes.iter().map(Vec::len).max().unwrap_or(0);
+    let mean = mean_landscape(landscapes, k);
+
+    let mut deviations: Vec<f64> = (0..resamples)
+        .into_par_iter()
+        .map(|i| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+            let mut acc = LandscapeAccumulator::new(k);
+            for _ in 0..landscapes.len() {
+                acc.add(&landscapes[rng.random_range(0..landscapes.len())]);
+            }
+            piecewiselinear::lp_distance_unchecked(&acc.mean(), &mean, f64::INFINITY)
+        })
+        .collect();
+    deviations.sort_by(f64::total_cmp);
+
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let index = ((level * resamples as f64).ceil() as usize).clamp(1, resamples) - 1;
+    let half_width = deviations[index];
+    Ok(ConfidenceBand {
+        lower: shift(&mean, -half_width),
+        upper: shift(&mean, half_width),
+        mean,
+        half_width,
+        level,
+    })
+}
+
+/// Compute the landscape of every diagram and run `bootstrap_band` on them
+///
+/// # Errors
+///
+/// Will return 'Err' if failed to compute persistencelandscape from any diagram
+pub fn diagrams_bootstrap_band(
+    diagrams: Vec<Vec<BirthDeath>>,
+    k: usize,
+    disable_filter: bool,
+    tolerance: Tolerance,
+    level: f64,
+    resamples: usize,
+    seed: u64,
+) -> Result<ConfidenceBand, Error> {
+    let landscapes = diagrams
+        .into_par_iter()
+        .map(|bd_pairs| rpls::pairs_to_landscape(bd_pairs, k, false, disable_filter, tolerance))
+        .collect::<Result<Vec<_>, _>>()?;
+    bootstrap_band(&landscapes, level, resamples, seed)
+}
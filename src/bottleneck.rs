@@ -0,0 +1,542 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use crate::birthdeath::{self, BirthDeath};
+use crate::error::Error;
+
+/// One side of a matched pair, either a pair of the diagram or the diagonal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Matched {
+    Pair(usize),
+    Diagonal,
+}
+
+/// Distance between two diagrams together with the matching that achieves it. Each entry is
+/// (point of the first diagram, point of the second diagram) using indices into the inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matching {
+    pub distance: f64,
+    pub pairs: Vec<(Matched, Matched)>,
+}
+
+pub(crate) fn point_distance(a: &BirthDeath, b: &BirthDeath) -> f64 {
+    (a.birth - b.birth).abs().max((a.death - b.death).abs())
+}
+
+pub(crate) fn diagonal_distance(a: &BirthDeath) -> f64 {
+    a.persistence().abs() / 2.0
+}
+
+// Indices of finite pairs and of pairs that never die, the latter sorted by birth
+pub(crate) fn split(diagram: &[BirthDeath]) -> (Vec<usize>, Vec<usize>) {
+    let finite = (0..diagram.len())
+        .filter(|&i| diagram[i].birth.is_finite() && diagram[i].death.is_finite())
+        .collect();
+    let mut essential: Vec<usize> = (0..diagram.len())
+        .filter(|&i| diagram[i].birth.is_finite() && diagram[i].death == f64::INFINITY)
+        .collect();
+    essential.sort_by(|&i, &j| diagram[i].birth.total_cmp(&diagram[j].birth));
+    (finite, essential)
+}
+
+// Pairs that never die can only be matched to each other, matching them in order of birth is
+// optimal. Returns the largest birth difference or infinity if the counts differ.
+pub(crate) fn match_essential(
+    a: &[BirthDeath],
+    ea: &[usize],
+    b: &[BirthDeath],
+    eb: &[usize],
+) -> (Vec<f64>, Vec<(Matched, Matched)>) {
+    let mut costs: Vec<f64> = ea
+        .iter()
+        .zip(eb)
+        .map(|(&i, &j)| (a[i].birth - b[j].birth).abs())
+        .collect();
+    let mut pairs: Vec<(Matched, Matched)> = ea
+        .iter()
+        .zip(eb)
+        .map(|(&i, &j)| (Matched::Pair(i), Matched::Pair(j)))
+        .collect();
+    for &i in ea.iter().skip(eb.len()) {
+        costs.push(f64::INFINITY);
+        pairs.push((Matched::Pair(i), Matched::Diagonal));
+    }
+    for &j in eb.iter().skip(ea.len()) {
+        costs.push(f64::INFINITY);
+        pairs.push((Matched::Diagonal, Matched::Pair(j)));
+    }
+    (costs, pairs)
+}
+
+// Static two dimensional tree over points of the second diagram. The node of the range lo..hi is
+// its middle position, so the tree is implicit in the order of `points`.
+struct KdTree {
+    points: Vec<(f64, f64, usize)>,
+    // Bounding box of the subtree of each node as [min birth, max birth, min death, max death]
+    bounds: Vec<[f64; 4]>,
+    // Points of the subtree of each node that have not been taken
+    counts: Vec<usize>,
+    present: Vec<bool>,
+}
+
+// Median splits alternating between birth and death
+fn split_nodes(points: &mut [(f64, f64, usize)], by_death: bool) {
+    if points.len() < 2 {
+        return;
+    }
+    let mid = points.len() / 2;
+    if by_death {
+        points.select_nth_unstable_by(mid, |p, q| p.1.total_cmp(&q.1));
+    } else {
+        points.select_nth_unstable_by(mid, |p, q| p.0.total_cmp(&q.0));
+    }
+    let (low, high) = points.split_at_mut(mid);
+    split_nodes(low, !by_death);
+    split_nodes(&mut high[1..], !by_death);
+}
+
+impl KdTree {
+    fn new(mut points: Vec<(f64, f64, usize)>) -> Self {
+        split_nodes(&mut points, false);
+        let n = points.len();
+        let mut tree = Self {
+            points,
+            bounds: vec![[0.0; 4]; n],
+            counts: vec![0; n],
+            present: vec![true; n],
+        };
+        tree.fill(0, n);
+        tree
+    }
+
+    fn fill(&mut self, lo: usize, hi: usize) -> Option<[f64; 4]> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = (lo + hi) / 2;
+        let (x, y, _) = self.points[mid];
+        let mut bounds = [x, x, y, y];
+        for child in [self.fill(lo, mid), self.fill(mid + 1, hi)].into_iter().flatten() {
+            bounds = [
+                bounds[0].min(child[0]),
+                bounds[1].max(child[1]),
+                bounds[2].min(child[2]),
+                bounds[3].max(child[3]),
+            ];
+        }
+        self.bounds[mid] = bounds;
+        self.counts[mid] = hi - lo;
+        Some(bounds)
+    }
+
+    // Takes the point at `position` out of the tree
+    fn remove(&mut self, position: usize) {
+        let (mut lo, mut hi) = (0, self.points.len());
+        loop {
+            let mid = (lo + hi) / 2;
+            self.counts[mid] -= 1;
+            match position.cmp(&mid) {
+                Ordering::Less => hi = mid,
+                Ordering::Greater => lo = mid + 1,
+                Ordering::Equal => break,
+            }
+        }
+        self.present[position] = false;
+    }
+
+    // Takes up to `limit` points within `r` of (x, y) out of the tree and pushes their ids to
+    // `out`, returns how many were taken. Differences are taken the way `point_distance` takes
+    // them and rounding is monotone, so the boxes never prune a point within `r`.
+    fn take(&mut self, lo: usize, hi: usize, (x, y): (f64, f64), r: f64, limit: usize, out: &mut Vec<usize>) -> usize {
+        let mid = (lo + hi) / 2;
+        if lo >= hi || self.counts[mid] == 0 || limit == 0 {
+            return 0;
+        }
+        let [min_x, max_x, min_y, max_y] = self.bounds[mid];
+        if min_x - x > r || x - max_x > r || min_y - y > r || y - max_y > r {
+            return 0;
+        }
+        let mut taken = 0;
+        let (px, py, id) = self.points[mid];
+        if self.present[mid] && (px - x).abs().max((py - y).abs()) <= r {
+            self.present[mid] = false;
+            out.push(id);
+            taken += 1;
+        }
+        taken += self.take(lo, mid, (x, y), r, limit - taken, out);
+        taken += self.take(mid + 1, hi, (x, y), r, limit - taken, out);
+        self.counts[mid] -= taken;
+        taken
+    }
+
+    // Pushes the distances from (x, y) that are above `lo` and at most `hi` to `out`. Gives up
+    // and returns false once `out` holds more than `limit` of them.
+    fn distances_between(&self, range: (usize, usize), (x, y): (f64, f64), lo: f64, hi: f64, limit: usize, out: &mut Vec<f64>) -> bool {
+        let (start, end) = range;
+        if start >= end {
+            return true;
+        }
+        let mid = (start + end) / 2;
+        let [min_x, max_x, min_y, max_y] = self.bounds[mid];
+        if min_x - x > hi || x - max_x > hi || min_y - y > hi || y - max_y > hi {
+            return true;
+        }
+        // The whole box is within `lo`
+        let farthest = (min_x - x).abs().max((max_x - x).abs()).max((min_y - y).abs()).max((max_y - y).abs());
+        if farthest <= lo {
+            return true;
+        }
+        let (px, py, _) = self.points[mid];
+        let d = (px - x).abs().max((py - y).abs());
+        if d > lo && d <= hi {
+            out.push(d);
+        }
+        out.len() <= limit
+            && self.distances_between((start, mid), (x, y), lo, hi, limit, out)
+            && self.distances_between((mid + 1, end), (x, y), lo, hi, limit, out)
+    }
+}
+
+// Bipartite graph on the first diagram plus the diagonal projections of the second (left) and the
+// second diagram plus the projections of the first (right). Left a_i is joined to right b_j when
+// they are within r, to its own projection when it is within r of the diagonal, and projections
+// are always joined to each other. Edges are never stored, a search takes the right neighbours of
+// a left vertex out of `Unreached` so each right vertex is reached once.
+struct ThresholdGraph<'a> {
+    a: Vec<&'a BirthDeath>,
+    b: Vec<&'a BirthDeath>,
+    r: f64,
+}
+
+// Right vertices a search has not reached yet, grouped by the layer they may be reached from.
+// Points of the second diagram are found by range search. Every projection of the second diagram
+// is joined to every projection of the first, so those are kept in a plain list.
+struct Unreached {
+    layers: Vec<usize>,
+    taken: Vec<bool>,
+    trees: Vec<KdTree>,
+    positions: Vec<usize>,
+    projections: Vec<Vec<usize>>,
+}
+
+impl Unreached {
+    // Right vertices with layer `usize::MAX` are left out of the search
+    fn new(graph: &ThresholdGraph, layers: Vec<usize>) -> Self {
+        let m = graph.b.len();
+        let depth = layers.iter().filter(|&&l| l != usize::MAX).max().map_or(0, |&l| l + 1);
+        let mut points = vec![Vec::new(); depth];
+        let mut projections = vec![Vec::new(); depth];
+        for (v, &layer) in layers.iter().enumerate() {
+            if layer == usize::MAX {
+                continue;
+            }
+            if v < m {
+                points[layer].push((graph.b[v].birth, graph.b[v].death, v));
+            } else {
+                projections[layer].push(v);
+            }
+        }
+        let trees: Vec<KdTree> = points.into_iter().map(KdTree::new).collect();
+        let mut positions = vec![usize::MAX; m];
+        for tree in &trees {
+            for (position, &(_, _, v)) in tree.points.iter().enumerate() {
+                positions[v] = position;
+            }
+        }
+        Self {
+            taken: vec![false; layers.len()],
+            layers,
+            trees,
+            positions,
+            projections,
+        }
+    }
+
+    // Takes the single right vertex `v` if it is in `layer` and has not been reached
+    fn take_one(&mut self, v: usize, layer: usize) -> bool {
+        if self.layers[v] != layer || self.taken[v] {
+            return false;
+        }
+        self.taken[v] = true;
+        if v < self.positions.len() {
+            self.trees[layer].remove(self.positions[v]);
+        }
+        true
+    }
+}
+
+impl ThresholdGraph<'_> {
+    fn size(&self) -> usize {
+        self.a.len() + self.b.len()
+    }
+
+    // Takes up to `limit` right neighbours of `left` from `layer` of `unreached` into `out`
+    fn take_neighbours(&self, left: usize, layer: usize, unreached: &mut Unreached, limit: usize, out: &mut Vec<usize>) {
+        let (n, m) = (self.a.len(), self.b.len());
+        out.clear();
+        if layer >= unreached.trees.len() {
+            return;
+        }
+        if left < n {
+            let p = self.a[left];
+            if diagonal_distance(p) <= self.r && unreached.take_one(m + left, layer) {
+                out.push(m + left);
+            }
+            let start = out.len();
+            let tree = &mut unreached.trees[layer];
+            let size = tree.points.len();
+            tree.take(0, size, (p.birth, p.death), self.r, limit - out.len(), out);
+            for &v in &out[start..] {
+                unreached.taken[v] = true;
+            }
+        } else {
+            let j = left - n;
+            if diagonal_distance(self.b[j]) <= self.r && unreached.take_one(j, layer) {
+                out.push(j);
+            }
+            // Projections taken as the partner of their own point are dropped here
+            while out.len() < limit {
+                match unreached.projections[layer].pop() {
+                    Some(v) if !unreached.taken[v] => {
+                        unreached.taken[v] = true;
+                        out.push(v);
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+        }
+    }
+
+    // Hopcroft-Karp maximum matching grown from `match_left`, which must be a matching of this
+    // graph. Returns the right vertex matched to each left vertex.
+    fn max_matching(&self, mut match_left: Vec<Option<usize>>) -> Vec<Option<usize>> {
+        let size = self.size();
+        let mut match_right: Vec<Option<usize>> = vec![None; size];
+        for (u, v) in match_left.iter().enumerate() {
+            if let Some(v) = *v {
+                match_right[v] = Some(u);
+            }
+        }
+        let mut dist = vec![usize::MAX; size];
+        let mut reached = Vec::new();
+
+        loop {
+            // Layer free left vertices by alternating path length, a right vertex belongs to the
+            // layer of the left vertex it is first reached from
+            let mut unreached = Unreached::new(self, vec![0; size]);
+            let mut layers = vec![usize::MAX; size];
+            let mut queue = VecDeque::new();
+            for u in 0..size {
+                if match_left[u].is_none() {
+                    dist[u] = 0;
+                    queue.push_back(u);
+                } else {
+                    dist[u] = usize::MAX;
+                }
+            }
+            let mut last = usize::MAX;
+            while let Some(u) = queue.pop_front() {
+                if dist[u] > last {
+                    break;
+                }
+                self.take_neighbours(u, 0, &mut unreached, usize::MAX, &mut reached);
+                for &v in &reached {
+                    layers[v] = dist[u];
+                    if let Some(w) = match_right[v] {
+                        dist[w] = dist[u] + 1;
+                        queue.push_back(w);
+                    } else {
+                        last = dist[u];
+                    }
+                }
+            }
+            if last == usize::MAX {
+                break;
+            }
+            let mut unreached = Unreached::new(self, layers);
+            for u in 0..size {
+                if match_left[u].is_none() {
+                    self.augment(u, &mut unreached, &mut match_left, &mut match_right, &mut dist);
+                }
+            }
+        }
+        match_left
+    }
+
+    // Depth first search along the layers for an augmenting path from the free left vertex `root`,
+    // flipping it if one is found. Right vertices are taken out of `unreached` as they are tried,
+    // a right vertex that failed once fails for the rest of the phase. The path is kept on an
+    // explicit stack since it can be as long as the diagram.
+    fn augment(
+        &self,
+        root: usize,
+        unreached: &mut Unreached,
+        match_left: &mut [Option<usize>],
+        match_right: &mut [Option<usize>],
+        dist: &mut [usize],
+    ) -> bool {
+        let mut path: Vec<(usize, usize)> = Vec::new();
+        let mut next = Vec::with_capacity(1);
+        let mut u = root;
+        loop {
+            self.take_neighbours(u, dist[u], unreached, 1, &mut next);
+            if let Some(&v) = next.first() {
+                match match_right[v] {
+                    None => {
+                        path.push((u, v));
+                        for &(left, right) in &path {
+                            match_left[left] = Some(right);
+                            match_right[right] = Some(left);
+                        }
+                        return true;
+                    }
+                    Some(w) if dist[w] == dist[u] + 1 => {
+                        path.push((u, v));
+                        u = w;
+                    }
+                    Some(_) => {}
+                }
+            } else {
+                // Dead end for this phase, go back to the previous left vertex
+                dist[u] = usize::MAX;
+                match path.pop() {
+                    Some((previous, _)) => u = previous,
+                    None => return false,
+                }
+            }
+        }
+    }
+}
+
+// Perfect matching of the threshold graph at `r` if there is one. Otherwise `below` becomes the
+// maximum matching at `r`, it stays a matching of every larger threshold.
+fn perfect(graph: &mut ThresholdGraph, r: f64, below: &mut Vec<Option<usize>>) -> Option<Vec<Option<usize>>> {
+    graph.r = r;
+    let matching = graph.max_matching(below.clone());
+    if matching.iter().all(Option::is_some) {
+        Some(matching)
+    } else {
+        *below = matching;
+        None
+    }
+}
+
+// Index of the smallest of the sorted `candidates` that admits a perfect matching along with the
+// matching, if any does
+fn search(
+    graph: &mut ThresholdGraph,
+    candidates: &[f64],
+    below: &mut Vec<Option<usize>>,
+) -> (usize, Option<Vec<Option<usize>>>) {
+    let (mut lo, mut hi) = (0, candidates.len());
+    let mut best = None;
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if let Some(matching) = perfect(graph, candidates[mid], below) {
+            best = Some(matching);
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    (lo, best)
+}
+
+/// Exact bottleneck distance between two diagrams under the L-infinity metric with its matching
+///
+/// The distance is one of the pairwise or diagonal distances, the smallest one that admits a
+/// perfect matching in the threshold graph. The sorted diagonal distances are binary searched
+/// first, then the interval left is halved until few enough pairwise distances fall inside it to
+/// list and binary search them, so memory stays linear in the number of pairs. Each matching is a
+/// Hopcroft-Karp grown from the one of the largest threshold that failed, with the neighbours of
+/// a pair found by range search in a two dimensional tree that every point is taken out of once
+/// per phase. Pairs that never die are matched among themselves in order of birth.
+///
+/// # Errors
+///
+/// Will return 'Err' if a pair of either diagram has a NaN coordinate or dies before it is born
+pub fn bottleneck(a: &[BirthDeath], b: &[BirthDeath]) -> Result<Matching, Error> {
+    birthdeath::check_pairs(a)?;
+    birthdeath::check_pairs(b)?;
+    let (fa, ea) = split(a);
+    let (fb, eb) = split(b);
+    let (essential_costs, mut pairs) = match_essential(a, &ea, b, &eb);
+
+    let mut graph = ThresholdGraph {
+        a: fa.iter().map(|&i| &a[i]).collect(),
+        b: fb.iter().map(|&j| &b[j]).collect(),
+        r: 0.0,
+    };
+    let mut diagonal: Vec<f64> = std::iter::once(0.0)
+        .chain(graph.a.iter().map(|p| diagonal_distance(p)))
+        .chain(graph.b.iter().map(|q| diagonal_distance(q)))
+        .collect();
+    diagonal.sort_by(f64::total_cmp);
+    diagonal.dedup();
+
+    // Maximum matching at the largest threshold known to fail, it stays a matching above it
+    let mut below = vec![None; graph.size()];
+    // The largest diagonal distance always admits a perfect matching through the diagonal
+    let (index, best) = search(&mut graph, &diagonal, &mut below);
+    let (mut hi, mut matching) = (diagonal[index.min(diagonal.len() - 1)], best.unwrap_or_default());
+    if index > 0 {
+        // The distance is `hi` or a pairwise distance above the last diagonal distance that failed
+        let mut lo = diagonal[index - 1];
+        let limit = 8 * graph.size();
+        let tree = KdTree::new(graph.b.iter().enumerate().map(|(j, q)| (q.birth, q.death, j)).collect());
+        let mut between = Vec::new();
+        loop {
+            between.clear();
+            let listed = graph.a.iter().all(|p| {
+                tree.distances_between((0, tree.points.len()), (p.birth, p.death), lo, hi, limit, &mut between)
+            });
+            if listed {
+                between.sort_by(f64::total_cmp);
+                between.dedup();
+                // `hi` is not listed again, it already has its matching
+                between.retain(|&d| d < hi);
+                if let (index, Some(found)) = search(&mut graph, &between, &mut below) {
+                    (hi, matching) = (between[index], found);
+                }
+                break;
+            }
+            // Too many to list, halve the interval in the order of the non-negative floats
+            let mid = f64::from_bits(lo.to_bits() + (hi.to_bits() - lo.to_bits()) / 2);
+            if let Some(found) = perfect(&mut graph, mid, &mut below) {
+                (hi, matching) = (mid, found);
+            } else {
+                lo = mid;
+            }
+        }
+    }
+
+    for (left, right) in matching.into_iter().enumerate() {
+        match (left < fa.len(), right) {
+            (true, Some(j)) if j < fb.len() => pairs.push((Matched::Pair(fa[left]), Matched::Pair(fb[j]))),
+            (true, _) => pairs.push((Matched::Pair(fa[left]), Matched::Diagonal)),
+            (false, Some(j)) if j < fb.len() => pairs.push((Matched::Diagonal, Matched::Pair(fb[j]))),
+            (false, _) => {}
+        }
+    }
+    Ok(Matching {
+        distance: essential_costs.into_iter().fold(hi, f64::max),
+        pairs,
+    })
+}
+
+/// Exact bottleneck distance between two diagrams, see `bottleneck`
+///
+/// # Errors
+///
+/// Will return 'Err' if a pair of either diagram has a NaN coordinate or dies before it is born
+pub fn bottleneck_distance(a: &[BirthDeath], b: &[BirthDeath]) -> Result<f64, Error> {
+    Ok(bottleneck(a, b)?.distance)
+}
@@ -0,0 +1,109 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::fmt;
+
+use crate::piecewiselinear;
+use crate::tolerance::Tolerance;
+
+/// Where one level of two landscapes is furthest apart
+///
+/// Levels are counted from 0, a level missing from one landscape is compared as zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelDiff {
+    pub level: usize,
+    pub x: f64,
+    /// Value of the level in the first landscape at `x`
+    pub left: f64,
+    /// Value of the level in the second landscape at `x`
+    pub right: f64,
+}
+
+impl LevelDiff {
+    #[must_use]
+    pub fn deviation(&self) -> f64 {
+        (self.left - self.right).abs()
+    }
+}
+
+impl fmt::Display for LevelDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Level {} differs by {} at x {}: {} against {}",
+            self.level,
+            self.deviation(),
+            self.x,
+            self.left,
+            self.right
+        )
+    }
+}
+
+/// Every level of two landscapes that is not the same under a tolerance, empty if they match
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diff {
+    pub levels: Vec<LevelDiff>,
+}
+
+impl Diff {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// The level with the largest deviation
+    #[must_use]
+    pub fn worst(&self) -> Option<&LevelDiff> {
+        self.levels.iter().max_by(|a, b| a.deviation().total_cmp(&b.deviation()))
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.worst() {
+            Some(worst) => write!(f, "{} levels differ, worst: {worst}", self.levels.len()),
+            None => write!(f, "Landscapes match"),
+        }
+    }
+}
+
+// Where two levels are furthest apart, if they are not the same under `tolerance` at every vertex.
+// Only relative tolerances can fail away from the largest deviation, so every vertex is checked.
+fn compare_level(level: usize, a: &[(f64,f64)], b: &[(f64,f64)], tolerance: Tolerance) -> Option<LevelDiff> {
+    let same = piecewiselinear::breakpoints(&[a, b])
+        .into_iter()
+        .all(|x| tolerance.approx_eq(piecewiselinear::evaluate(a, x), piecewiselinear::evaluate(b, x)));
+    if same {
+        return None;
+    }
+    let (_, x) = piecewiselinear::sup_distance(a, b);
+    Some(LevelDiff {
+        level,
+        x,
+        left: piecewiselinear::evaluate(a, x),
+        right: piecewiselinear::evaluate(b, x),
+    })
+}
+
+/// Compare two landscapes as functions, level by level
+///
+/// Each level is compared at the vertices of both landscapes, so the same function with different
+/// vertices matches. For an absolute tolerance this is the sup norm distance of each level, which
+/// is taken at a vertex of one of the two.
+#[must_use]
+pub fn compare(a: &[Vec<(f64,f64)>], b: &[Vec<(f64,f64)>], tolerance: Tolerance) -> Diff {
+    let empty = Vec::new();
+    let levels = (0..a.len().max(b.len()))
+        .filter_map(|level| {
+            let left = a.get(level).unwrap_or(&empty);
+            let right = b.get(level).unwrap_or(&empty);
+            compare_level(level, left, right, tolerance)
+        })
+        .collect();
+    Diff { levels }
+}
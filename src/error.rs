@@ -0,0 +1,96 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::fmt;
+
+/// Sweep event that was being handled when an invariant was broken
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventContext {
+    /// Up, Down, Death or Intersection
+    pub kind: &'static str,
+    pub x: f64,
+    pub y: f64,
+    pub mountain: usize,
+    /// The other mountain of an intersection
+    pub neighbor: Option<usize>,
+}
+
+impl fmt::Display for EventContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} event at ({}, {}) of mountain {}", self.kind, self.x, self.y, self.mountain)?;
+        if let Some(neighbor) = self.neighbor {
+            write!(f, " and mountain {neighbor}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors returned by every public entry point of the library
+#[derive(Debug)]
+pub enum Error {
+    /// No usable birth death pairs were given
+    EmptyInput,
+    /// The pair at `index` has a NaN coordinate or dies before it is born
+    InvalidPair { index: usize, birth: f64, death: f64 },
+    /// Line `line` (counted from 1) of a diagram file is not a space separated pair of numbers
+    Parse { line: usize, content: String },
+    /// An argument is outside of the range the function accepts
+    InvalidArgument(String),
+    /// Internal state that should be impossible, this is a bug
+    Invariant { message: String, event: Option<EventContext> },
+    /// An approximation could not get within the `requested` relative error, `achieved` is the
+    /// bound it did reach
+    NotConverged { requested: f64, achieved: f64 },
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyInput => write!(f, "No BirthDeath pairs found"),
+            Self::InvalidPair { index, birth, death } => {
+                write!(f, "Invalid BirthDeath pair {index}: ({birth}, {death})")
+            }
+            Self::Parse { line, content } => write!(f, "Could not parse line {line}: {content:?}"),
+            Self::InvalidArgument(message) => f.write_str(message),
+            Self::Invariant { message, event: Some(event) } => write!(f, "{message} on {event}"),
+            Self::Invariant { message, event: None } => write!(f, "{message}"),
+            Self::NotConverged { requested, achieved } => {
+                write!(f, "Stopped at a relative error of {achieved}, above the requested {requested}")
+            }
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Self::Io(e.into())
+    }
+}
+
+#[cfg(feature = "plot")]
+impl<E: std::error::Error + Send + Sync + 'static> From<plotters::drawing::DrawingAreaErrorKind<E>> for Error {
+    fn from(e: plotters::drawing::DrawingAreaErrorKind<E>) -> Self {
+        Self::Io(std::io::Error::other(e))
+    }
+}
@@ -0,0 +1,151 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use csv::Writer;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::Error;
+
+/// File formats a matrix can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixFormat {
+    Csv,
+    Npy,
+}
+
+impl MatrixFormat {
+    /// `Npy` for paths ending in `.npy`, `Csv` otherwise
+    #[must_use]
+    pub fn from_path(path: &str) -> Self {
+        if Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("npy")) {
+            Self::Npy
+        } else {
+            Self::Csv
+        }
+    }
+}
+
+/// Write each level as rows of x,y with an empty row between levels
+///
+/// # Errors
+///
+/// Will return 'Err' if `path` can not be written
+pub fn write_landscape_csv(path: &str, landscapes: &[Vec<(f64,f64)>]) -> Result<(), Error> {
+    let mut wtr = Writer::from_path(path)?;
+    for landscape in landscapes {
+        for point in landscape {
+            wtr.write_record(&[point.0.to_string(), point.1.to_string()])?;
+        }
+        wtr.write_record(["", ""])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Read a landscape written by `write_landscape_csv`
+///
+/// # Errors
+///
+/// Will return 'Err' if `path` can not be read or a row is neither x,y nor empty
+pub fn read_landscape_csv(path: &str) -> Result<Vec<Vec<(f64,f64)>>, Error> {
+    let mut landscapes = Vec::new();
+    let mut level = Vec::new();
+    for (i, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+        let parse_error = || Error::Parse { line: i + 1, content: line.to_string() };
+        let (x, y) = line.split_once(',').ok_or_else(parse_error)?;
+        if x.trim().is_empty() && y.trim().is_empty() {
+            landscapes.push(std::mem::take(&mut level));
+            continue;
+        }
+        let x = x.trim().parse().map_err(|_| parse_error())?;
+        let y = y.trim().parse().map_err(|_| parse_error())?;
+        level.push((x, y));
+    }
+    if !level.is_empty() {
+        landscapes.push(level);
+    }
+    Ok(landscapes)
+}
+
+/// Write a row major matrix in `format`
+///
+/// # Errors
+///
+/// Will return 'Err' if `path` can not be written
+pub fn write_matrix(path: &str, matrix: &[Vec<f64>], format: MatrixFormat) -> Result<(), Error> {
+    match format {
+        MatrixFormat::Csv => {
+            let mut wtr = Writer::from_path(path)?;
+            for row in matrix {
+                wtr.write_record(row.iter().map(ToString::to_string))?;
+            }
+            wtr.flush()?;
+        }
+        MatrixFormat::Npy => {
+            let cols = matrix.first().map_or(0, Vec::len);
+            let mut npy = NpyMatrix::create(path, matrix.len(), cols)?;
+            for (i, row) in matrix.iter().enumerate() {
+                npy.write_at(i, 0, row)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Little endian f64 `.npy` file of a fixed shape that can be filled in any order
+pub(crate) struct NpyMatrix {
+    file: File,
+    header_len: u64,
+    cols: usize,
+}
+
+impl NpyMatrix {
+    pub(crate) fn create(path: &str, rows: usize, cols: usize) -> Result<Self, std::io::Error> {
+        let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': ({rows}, {cols}), }}");
+        // magic + version + header length + header + newline is padded to a multiple of 64
+        let unpadded = 10 + header.len() + 1;
+        header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+        header.push('\n');
+        let header_size = u16::try_from(header.len())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "npy header too long"))?;
+
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.write_all(b"\x93NUMPY\x01\x00")?;
+        file.write_all(&header_size.to_le_bytes())?;
+        file.write_all(header.as_bytes())?;
+        let header_len = 10 + header.len() as u64;
+        file.set_len(header_len + (rows * cols * 8) as u64)?;
+        Ok(Self { file, header_len, cols })
+    }
+
+    /// Write `values` into `row` starting at column `col`
+    pub(crate) fn write_at(&mut self, row: usize, col: usize, values: &[f64]) -> Result<(), std::io::Error> {
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.file.seek(SeekFrom::Start(self.header_len + ((row * self.cols + col) * 8) as u64))?;
+        self.file.write_all(&bytes)
+    }
+
+    /// Rewrite the matrix as CSV at `path` one row at a time
+    pub(crate) fn write_csv(&mut self, path: &str) -> Result<(), Error> {
+        self.file.seek(SeekFrom::Start(self.header_len))?;
+        let mut reader = BufReader::new(&self.file);
+        let mut wtr = Writer::from_writer(BufWriter::new(File::create(path)?));
+        let mut row = vec![0_u8; self.cols * 8];
+        let rows = (self.file.metadata()?.len() - self.header_len) / (row.len().max(1) as u64);
+        for _ in 0..rows {
+            reader.read_exact(&mut row)?;
+            wtr.write_record(
+                row.chunks_exact(8)
+                    .map(|b| f64::from_le_bytes(b.try_into().unwrap_or_default()).to_string()),
+            )?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use rayon::prelude::*;
+use crate::birthdeath::BirthDeath;
+use crate::error::Error;
+use crate::fileio::{MatrixFormat, NpyMatrix};
+use crate::piecewiselinear;
+
+/// Symmetric positive definite kernel between two items
+pub trait Kernel<T>: Sync {
+    fn evaluate(&self, a: &T, b: &T) -> f64;
+
+    /// Check the parameters of the kernel, `evaluate` assumes they are valid
+    ///
+    /// # Errors
+    ///
+    /// Will return 'Err' if a parameter is outside of the range the kernel accepts
+    fn check(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+// Every Gaussian style kernel divides by sigma
+fn check_sigma(sigma: f64) -> Result<(), Error> {
+    if sigma.is_nan() || sigma <= 0.0 {
+        return Err(Error::InvalidArgument("Kernel sigma must be positive".to_string()));
+    }
+    Ok(())
+}
+
+/// Kernels on landscapes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LandscapeKernel {
+    /// L2 inner product summed over levels
+    Linear,
+    /// exp(-d^2 / (2 sigma^2)) where d is the Lp distance between the landscapes, `p` >= 1
+    Gaussian { sigma: f64, p: f64 },
+}
+
+impl Kernel<Vec<Vec<(f64,f64)>>> for LandscapeKernel {
+    fn evaluate(&self, a: &Vec<Vec<(f64,f64)>>, b: &Vec<Vec<(f64,f64)>>) -> f64 {
+        match *self {
+            Self::Linear => piecewiselinear::inner_product(a, b),
+            Self::Gaussian { sigma, p } => {
+                let d = piecewiselinear::lp_distance_unchecked(a, b, p);
+                (-d * d / (2.0 * sigma * sigma)).exp()
+            }
+        }
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        match *self {
+            Self::Linear => Ok(()),
+            Self::Gaussian { sigma, p } => {
+                check_sigma(sigma)?;
+                piecewiselinear::check_order(p)
+            }
+        }
+    }
+}
+
+/// Sliced Wasserstein kernel exp(-SW / (2 sigma^2)) on diagrams, where SW is averaged over
+/// `directions` evenly spaced lines through the origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlicedWassersteinKernel {
+    pub sigma: f64,
+    pub directions: usize,
+}
+
+/// Persistence scale-space kernel on diagrams, the heat diffusion of each diagram with the
+/// diagonal as an absorbing boundary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PersistenceScaleSpaceKernel {
+    pub sigma: f64,
+}
+
+// Both diagram kernels only see pairs with a finite birth and death
+fn finite_points(diagram: &[BirthDeath]) -> impl Iterator<Item = (f64, f64)> + '_ {
+    diagram
+        .iter()
+        .filter(|p| p.birth.is_finite() && p.death.is_finite())
+        .map(|p| (p.birth, p.death))
+}
+
+impl SlicedWassersteinKernel {
+    /// Sliced Wasserstein distance between two diagrams
+    ///
+    /// Each diagram is completed with the diagonal projections of the other so both have the
+    /// same number of points, then on every direction the sorted projections are matched in order.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn distance(&self, a: &[BirthDeath], b: &[BirthDeath]) -> f64 {
+        let diagonal = |(x, y): (f64, f64)| ((x + y) / 2.0, (x + y) / 2.0);
+        let a_full: Vec<(f64, f64)> = finite_points(a).chain(finite_points(b).map(diagonal)).collect();
+        let b_full: Vec<(f64, f64)> = finite_points(b).chain(finite_points(a).map(diagonal)).collect();
+        if a_full.is_empty() || self.directions == 0 {
+            return 0.0;
+        }
+        let step = std::f64::consts::PI / self.directions as f64;
+        let mut pa = vec![0.0; a_full.len()];
+        let mut pb = vec![0.0; b_full.len()];
+        let total: f64 = (0..self.directions)
+            .map(|i| {
+                let theta = (i as f64).mul_add(step, -std::f64::consts::FRAC_PI_2);
+                let (sin, cos) = theta.sin_cos();
+                for (v, &(x, y)) in pa.iter_mut().zip(&a_full) {
+                    *v = x.mul_add(cos, y * sin);
+                }
+                for (v, &(x, y)) in pb.iter_mut().zip(&b_full) {
+                    *v = x.mul_add(cos, y * sin);
+                }
+                pa.sort_by(f64::total_cmp);
+                pb.sort_by(f64::total_cmp);
+                pa.iter().zip(&pb).map(|(u, v)| (u - v).abs()).sum::<f64>()
+            })
+            .sum();
+        total / self.directions as f64
+    }
+}
+
+impl Kernel<Vec<BirthDeath>> for SlicedWassersteinKernel {
+    fn evaluate(&self, a: &Vec<BirthDeath>, b: &Vec<BirthDeath>) -> f64 {
+        (-self.distance(a, b) / (2.0 * self.sigma * self.sigma)).exp()
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        check_sigma(self.sigma)?;
+        if self.directions == 0 {
+            return Err(Error::InvalidArgument("Sliced Wasserstein needs at least one direction".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl Kernel<Vec<BirthDeath>> for PersistenceScaleSpaceKernel {
+    fn evaluate(&self, a: &Vec<BirthDeath>, b: &Vec<BirthDeath>) -> f64 {
+        let scale = 8.0 * self.sigma;
+        let total: f64 = finite_points(a)
+            .flat_map(|p| finite_points(b).map(move |q| (p, q)))
+            .map(|((px, py), (qx, qy))| {
+                let direct = (px - qx).mul_add(px - qx, (py - qy) * (py - qy));
+                // Distance to the mirror image of q across the diagonal
+                let mirrored = (px - qy).mul_add(px - qy, (py - qx) * (py - qx));
+                (-direct / scale).exp() - (-mirrored / scale).exp()
+            })
+            .sum();
+        total / (std::f64::consts::PI * scale)
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        check_sigma(self.sigma)
+    }
+}
+
+// Entries (i, j) with j >= i for rows `rows` and columns `cols`
+fn upper_tile<T: Sync, K: Kernel<T>>(
+    items: &[T],
+    kernel: &K,
+    rows: std::ops::Range<usize>,
+    cols: std::ops::Range<usize>,
+) -> Vec<Vec<f64>> {
+    rows.into_par_iter()
+        .map(|i| {
+            cols.clone()
+                .map(|j| if j < i { f64::NAN } else { kernel.evaluate(&items[i], &items[j]) })
+                .collect()
+        })
+        .collect()
+}
+
+/// Gram matrix K[i][j] = kernel(items[i], items[j]) held in memory. Only the upper triangle is
+/// evaluated.
+///
+/// # Errors
+///
+/// Will return 'Err' if the parameters of `kernel` are invalid, see `Kernel::check`
+pub fn gram_matrix<T: Sync, K: Kernel<T>>(items: &[T], kernel: &K) -> Result<Vec<Vec<f64>>, Error> {
+    kernel.check()?;
+    let n = items.len();
+    let mut matrix = upper_tile(items, kernel, 0..n, 0..n);
+    for i in 0..n {
+        for j in 0..i {
+            matrix[i][j] = matrix[j][i];
+        }
+    }
+    Ok(matrix)
+}
+
+/// Write the Gram matrix of `items` to `path` without holding it in memory
+///
+/// The matrix is computed in `block_size` square tiles of the upper triangle, each tile is
+/// written along with its transpose so only one tile is in memory at a time. CSV output is
+/// assembled in a temporary `.npy` next to `path` and converted row by row.
+///
+/// # Errors
+///
+/// Will return 'Err' if `path` can not be written, `block_size` is zero or the parameters of
+/// `kernel` are invalid
+pub fn write_gram_matrix<T: Sync, K: Kernel<T>>(
+    path: &str,
+    items: &[T],
+    kernel: &K,
+    block_size: usize,
+    format: MatrixFormat,
+) -> Result<(), Error> {
+    if block_size == 0 {
+        return Err(Error::InvalidArgument("Block size must be positive".to_string()));
+    }
+    kernel.check()?;
+    let n = items.len();
+    let npy_path = match format {
+        MatrixFormat::Npy => path.to_string(),
+        MatrixFormat::Csv => format!("{path}.part.npy"),
+    };
+    let mut npy = NpyMatrix::create(&npy_path, n, n)?;
+    for r0 in (0..n).step_by(block_size) {
+        let r1 = (r0 + block_size).min(n);
+        for c0 in (r0..n).step_by(block_size) {
+            let c1 = (c0 + block_size).min(n);
+            let mut tile = upper_tile(items, kernel, r0..r1, c0..c1);
+            if r0 == c0 {
+                // Diagonal tiles only hold the upper triangle, fill the rest from the transpose
+                for i in 0..tile.len() {
+                    for j in 0..i {
+                        tile[i][j] = tile[j][i];
+                    }
+                }
+            } else {
+                for j in 0..(c1 - c0) {
+                    let column: Vec<f64> = tile.iter().map(|row| row[j]).collect();
+                    npy.write_at(c0 + j, r0, &column)?;
+                }
+            }
+            for (i, row) in tile.iter().enumerate() {
+                npy.write_at(r0 + i, c0, row)?;
+            }
+        }
+    }
+    if format == MatrixFormat::Csv {
+        npy.write_csv(path)?;
+        drop(npy);
+        std::fs::remove_file(npy_path)?;
+    }
+    Ok(())
+}
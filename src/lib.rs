@@ -1,6 +1,28 @@
+pub mod error;
+pub mod tolerance;
 pub mod birthdeath;
 pub mod persistencelandscape;
 pub mod barcode;
 #[cfg(feature = "plot")]
 pub mod plot;
 pub mod rpls;
+pub mod piecewiselinear;
+pub mod accumulator;
+pub mod pointwise;
+pub mod permutation;
+pub mod bootstrap;
+pub mod fileio;
+pub mod kernel;
+pub mod silhouette;
+pub mod stepfunction;
+pub mod betticurve;
+pub mod lifetime;
+pub mod persistenceimage;
+pub mod statistics;
+pub mod bottleneck;
+pub mod wasserstein;
+pub mod summary;
+pub mod basis;
+pub mod validate;
+pub mod reference;
+pub mod compare;
@@ -0,0 +1,49 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use float_ord::FloatOrd;
+
+use crate::birthdeath::BirthDeath;
+
+// Running sum of the lifetimes of finite pairs ordered by `key`, one step per distinct key. Pairs
+// that never die have an infinite lifetime and are left out.
+fn accumulate<F>(bd_pairs: &[BirthDeath], key: F) -> Vec<(f64, f64)>
+where
+    F: Fn(&BirthDeath) -> f64,
+{
+    let mut events: Vec<(f64, f64)> = bd_pairs
+        .iter()
+        .filter(|bd| bd.birth.is_finite() && bd.death.is_finite())
+        .map(|bd| (key(bd), bd.persistence()))
+        .collect();
+    events.sort_by_key(|(x, _)| FloatOrd(*x));
+    let mut steps: Vec<(f64, f64)> = Vec::new();
+    let mut total = 0.0;
+    for (x, lifetime) in events {
+        total += lifetime;
+        match steps.last_mut() {
+            Some(last) if FloatOrd(last.0) == FloatOrd(x) => last.1 = total,
+            _ => steps.push((x, total)),
+        }
+    }
+    steps
+}
+
+/// Accumulated persistence function, the total lifetime of the features whose midlife
+/// (birth + death) / 2 is at most m, as a step function, see `stepfunction` for evaluating and
+/// sampling it
+#[must_use]
+pub fn accumulated_persistence(bd_pairs: &[BirthDeath]) -> Vec<(f64, f64)> {
+    accumulate(bd_pairs, |bd| (bd.birth + bd.death) / 2.0)
+}
+
+/// Cumulative lifetime curve, the total lifetime of the features that died at or before t, as a
+/// step function
+#[must_use]
+pub fn cumulative_lifetime(bd_pairs: &[BirthDeath]) -> Vec<(f64, f64)> {
+    accumulate(bd_pairs, |bd| bd.death)
+}
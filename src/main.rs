@@ -5,24 +5,26 @@
      clippy::cargo,
  )]
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use csv::Writer;
 use std::error::Error;
-use std::fs;
 use std::time::Instant;
 
 /// Generates the PL for a set of birth death pairs
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
     /// Name of the file to read birth death pairs from
-    #[clap(short, long, value_parser)]
-    name: String,
+    #[clap(short, long, value_parser, required = true)]
+    name: Option<String>,
     /// Max kth-landscape to calculate
     #[clap(short, long, value_parser, default_value_t = 1)]
     k: usize,
     /// Height of output image
-    #[clap(short, long, value_parser, default_value_t = 720)]
+    #[clap(short = 'H', long, value_parser, default_value_t = 720)]
     height: u32,
     /// Width of output image
     #[clap(short, long, value_parser, default_value_t = 1280)]
@@ -37,47 +39,297 @@ struct Args {
     #[clap(short, long, value_parser, default_value = "")]
     csv: String,
     /// Disables filtering
-    #[clap(short, long, value_parser)]
+    #[clap(short = 'D', long, value_parser)]
     disable_filter: bool,
+    /// When values are the same: abs:<distance>, rel:<fraction> or ulp:<count>
+    #[clap(short, long, value_parser, default_value_t = fast_pl::tolerance::Tolerance::default())]
+    tolerance: fast_pl::tolerance::Tolerance,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Permutation test for a difference between the mean landscapes of two groups of diagrams
+    PermutationTest {
+        /// Files of birth death pairs in the first group
+        #[clap(short = 'a', long, value_parser, num_args = 1.., required = true)]
+        group_a: Vec<String>,
+        /// Files of birth death pairs in the second group
+        #[clap(short = 'b', long, value_parser, num_args = 1.., required = true)]
+        group_b: Vec<String>,
+        /// Max kth-landscape to calculate
+        #[clap(short, long, value_parser, default_value_t = 1)]
+        k: usize,
+        /// Order of the Lp distance between the mean landscapes
+        #[clap(short, long, value_parser, default_value_t = 2.0)]
+        p: f64,
+        /// Number of random relabellings
+        #[clap(short = 'n', long, value_parser, default_value_t = 1000)]
+        permutations: usize,
+        /// Seed for the random relabellings
+        #[clap(short, long, value_parser, default_value_t = 0)]
+        seed: u64,
+        /// Disables filtering
+        #[clap(short = 'D', long, value_parser)]
+        disable_filter: bool,
+        /// When values are the same: abs:<distance>, rel:<fraction> or ulp:<count>
+        #[clap(short, long, value_parser, default_value_t = fast_pl::tolerance::Tolerance::default())]
+        tolerance: fast_pl::tolerance::Tolerance,
+    },
+    /// Bootstrap a uniform confidence band for the mean landscape of a set of diagrams
+    Bootstrap {
+        /// Files of birth death pairs to average
+        #[clap(short, long, value_parser, num_args = 1.., required = true)]
+        files: Vec<String>,
+        /// Max kth-landscape to calculate
+        #[clap(short, long, value_parser, default_value_t = 1)]
+        k: usize,
+        /// Confidence level of the band
+        #[clap(short, long, value_parser, default_value_t = 0.95)]
+        level: f64,
+        /// Number of bootstrap resamples
+        #[clap(short = 'n', long, value_parser, default_value_t = 1000)]
+        resamples: usize,
+        /// Seed for the resampling
+        #[clap(short, long, value_parser, default_value_t = 0)]
+        seed: u64,
+        /// Height of output image
+        #[clap(short = 'H', long, value_parser, default_value_t = 720)]
+        height: u32,
+        /// Width of output image
+        #[clap(short, long, value_parser, default_value_t = 1280)]
+        width: u32,
+        /// Save output image of the mean, lower and upper curves
+        #[clap(short, long, value_parser)]
+        graph: bool,
+        /// Save mean, lower and upper curves to CSV
+        #[clap(short, long, value_parser, default_value = "")]
+        csv: String,
+        /// Disables filtering
+        #[clap(short = 'D', long, value_parser)]
+        disable_filter: bool,
+        /// When values are the same: abs:<distance>, rel:<fraction> or ulp:<count>
+        #[clap(short, long, value_parser, default_value_t = fast_pl::tolerance::Tolerance::default())]
+        tolerance: fast_pl::tolerance::Tolerance,
+    },
+    /// Summary statistics of a diagram
+    Stats {
+        /// Name of the file to read birth death pairs from
+        #[clap(short, long, value_parser)]
+        name: String,
+        /// Order of the total persistence
+        #[clap(short, long, value_parser, default_value_t = 1.0)]
+        p: f64,
+        /// Output format
+        #[clap(short, long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Feature vectors of a summary of each diagram sampled on an evenly spaced grid
+    Vectorize {
+        /// Files of birth death pairs, one row per file
+        #[clap(short, long, value_parser, num_args = 1.., required = true)]
+        files: Vec<String>,
+        /// Summary to vectorize: landscape, silhouette, betti, apf, lifetime or image
+        #[clap(short, long, value_parser, default_value = "landscape")]
+        summary: fast_pl::summary::SummaryKind,
+        /// Max kth-landscape to calculate
+        #[clap(short, long, value_parser, default_value_t = 1)]
+        k: usize,
+        /// Power of the silhouette weights
+        #[clap(short, long, value_parser, default_value_t = 1.0)]
+        power: f64,
+        /// Start of the grid
+        #[clap(long, value_parser, default_value_t = 0.0)]
+        start: f64,
+        /// End of the grid
+        #[clap(long, value_parser, default_value_t = 1.0)]
+        stop: f64,
+        /// Number of grid points
+        #[clap(short = 'n', long, value_parser, default_value_t = 100)]
+        num: usize,
+        /// Standard deviation of the Gaussians of the persistence image
+        #[clap(long, value_parser, default_value_t = 0.1)]
+        sigma: f64,
+        /// Pixels of the persistence image along birth and along persistence
+        #[clap(long, value_parser, num_args = 2, value_names = ["BIRTH", "PERSISTENCE"], default_values_t = [20, 20])]
+        resolution: Vec<usize>,
+        /// Birth and persistence ranges of the persistence image, covering every diagram if not given
+        #[clap(long, value_parser, num_args = 4, allow_negative_numbers = true,
+               value_names = ["BIRTH_MIN", "BIRTH_MAX", "PERSISTENCE_MIN", "PERSISTENCE_MAX"])]
+        bounds: Option<Vec<f64>>,
+        /// Save the vectors to a .csv or .npy file instead of printing them
+        #[clap(short, long, value_parser, default_value = "")]
+        output: String,
+        /// Disables filtering
+        #[clap(short = 'D', long, value_parser)]
+        disable_filter: bool,
+        /// When values are the same: abs:<distance>, rel:<fraction> or ulp:<count>
+        #[clap(short, long, value_parser, default_value_t = fast_pl::tolerance::Tolerance::default())]
+        tolerance: fast_pl::tolerance::Tolerance,
+    },
+    /// Check that a landscape saved as CSV is a valid persistence landscape
+    Validate {
+        /// CSV file of the landscape, as written by --csv
+        #[clap(short, long, value_parser)]
+        name: String,
+        /// When values are the same: abs:<distance>, rel:<fraction> or ulp:<count>
+        #[clap(short, long, value_parser, default_value_t = fast_pl::tolerance::Tolerance::default())]
+        tolerance: fast_pl::tolerance::Tolerance,
+    },
+    /// Compare two landscapes saved as CSV level by level
+    Compare {
+        /// CSV file of the first landscape, as written by --csv
+        #[clap(short, long, value_parser)]
+        left: String,
+        /// CSV file of the second landscape
+        #[clap(short, long, value_parser)]
+        right: String,
+        /// When values are the same: abs:<distance>, rel:<fraction> or ulp:<count>
+        #[clap(short, long, value_parser, default_value_t = fast_pl::tolerance::Tolerance::default())]
+        tolerance: fast_pl::tolerance::Tolerance,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    Json,
+    Csv,
+}
+
+fn read_files(names: &[String]) -> Result<Vec<Vec<fast_pl::birthdeath::BirthDeath>>, Box<dyn Error>> {
+    Ok(names
+        .iter()
+        .map(|name| fast_pl::birthdeath::read_file(name))
+        .collect::<Result<_, _>>()?)
+}
+
+fn run_command(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::PermutationTest { group_a, group_b, k, p, permutations, seed, disable_filter, tolerance } => {
+            let result = fast_pl::permutation::diagrams_permutation_test(
+                read_files(&group_a)?,
+                read_files(&group_b)?,
+                k,
+                disable_filter,
+                tolerance,
+                p,
+                permutations,
+                seed,
+            )?;
+            println!("Statistic: {}", result.statistic);
+            println!("p-value: {}", result.p_value);
+        }
+        Command::Bootstrap { files, k, level, resamples, seed, height, width, graph, csv, disable_filter, tolerance } => {
+            let band = fast_pl::bootstrap::diagrams_bootstrap_band(
+                read_files(&files)?,
+                k,
+                disable_filter,
+                tolerance,
+                level,
+                resamples,
+                seed,
+            )?;
+            println!("Half width: {}", band.half_width);
+            if !csv.is_empty() {
+                fast_pl::fileio::write_landscape_csv(&csv, &band.to_plot())?;
+            }
+            #[cfg(feature = "plot")]
+            if graph {
+                return Ok(fast_pl::plot::landscape(band.to_plot(), height, width)?);
+            }
+            #[cfg(not(feature = "plot"))]
+            let _ = (graph, height, width);
+        }
+        Command::Stats { name, p, format } => {
+            let stats = fast_pl::statistics::summarize(&fast_pl::birthdeath::read_file_with_infinite(&name)?, p);
+            match format {
+                Format::Json => println!("{}", stats.to_json()),
+                Format::Csv => {
+                    let mut wtr = Writer::from_writer(std::io::stdout());
+                    wtr.write_record(fast_pl::statistics::DiagramStats::fields())?;
+                    wtr.write_record(stats.values().iter().map(ToString::to_string))?;
+                    wtr.flush()?;
+                }
+            }
+        }
+        Command::Vectorize {
+            files, summary, k, power, start, stop, num, sigma, resolution, bounds, output, disable_filter, tolerance,
+        } => {
+            let image = fast_pl::persistenceimage::PersistenceImageConfig {
+                sigma,
+                resolution: (resolution[0], resolution[1]),
+                bounds: bounds.map(|b| ((b[0], b[1]), (b[2], b[3]))),
+                ..Default::default()
+            };
+            let options = fast_pl::summary::SummaryOptions { k, disable_filter, power, image, tolerance };
+            let grid = fast_pl::stepfunction::linspace(start, stop, num);
+            let vectors = summary.vectorize(read_files(&files)?, &options, &grid)?;
+            if output.is_empty() {
+                let mut wtr = Writer::from_writer(std::io::stdout());
+                for vector in &vectors {
+                    wtr.write_record(vector.iter().map(ToString::to_string))?;
+                }
+                wtr.flush()?;
+            } else {
+                fast_pl::fileio::write_matrix(&output, &vectors, fast_pl::fileio::MatrixFormat::from_path(&output))?;
+            }
+        }
+        Command::Validate { name, tolerance } => {
+            let landscapes = fast_pl::fileio::read_landscape_csv(&name)?;
+            let violations = fast_pl::validate::validate(&landscapes, tolerance);
+            for violation in &violations {
+                println!("{violation}");
+            }
+            if !violations.is_empty() {
+                return Err(format!("{} violations found", violations.len()).into());
+            }
+            println!("Valid landscape with {} levels", landscapes.len());
+        }
+        Command::Compare { left, right, tolerance } => {
+            let left = fast_pl::fileio::read_landscape_csv(&left)?;
+            let right = fast_pl::fileio::read_landscape_csv(&right)?;
+            let diff = fast_pl::compare::compare(&left, &right, tolerance);
+            for level in &diff.levels {
+                println!("{level}");
+            }
+            if !diff.is_empty() {
+                return Err(diff.to_string().into());
+            }
+            println!("Landscapes match within {tolerance}");
+        }
+    }
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
+    if let Some(command) = args.command {
+        return run_command(command);
+    }
 
     let now = Instant::now();
-    let bd_paris: Vec<fast_pl::birthdeath::BirthDeath> = fs::read_to_string(args.name)?
-        .lines()
-        .filter(|s| !s.contains("inf") && !s.is_empty())
-        .map(str::parse)
-        .map(Result::unwrap)
-        .collect();
+    let name = args.name.ok_or("No file of birth death pairs given")?;
+    let bd_paris = fast_pl::birthdeath::read_file(&name)?;
 
-    let landscapes = fast_pl::rpls::pairs_to_landscape(bd_paris, args.k, args.debug, args.disable_filter)?;
+    let landscapes = fast_pl::rpls::pairs_to_landscape(bd_paris, args.k, args.debug, args.disable_filter, args.tolerance)?;
 
     let elapsed = now.elapsed();
     println!("Elapsed: {elapsed:.?}");
 
     if !args.csv.is_empty() {
-        let mut wtr = Writer::from_path(args.csv)?;
-        for landscape in &landscapes {
-            for point in landscape {
-                wtr.write_record(&[point.0.to_string(), point.1.to_string()])?;
-            }
-            wtr.write_record(["", ""])?;
-        }
-        wtr.flush()?;
+        fast_pl::fileio::write_landscape_csv(&args.csv, &landscapes)?;
     }
     #[cfg(feature = "plot")]
     if args.graph {
-        return fast_pl::plot::landscape(landscapes, args.height, args.width);
+        return Ok(fast_pl::plot::landscape(landscapes, args.height, args.width)?);
     }
     if args.debug{
-        println!("Area: {}", fast_pl::rpls::l2_norm(&landscapes));
+        println!("Area: {}", fast_pl::rpls::l2_norm(&landscapes)?);
     }
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
 mod tests {
     fn test_runner(k: usize, bd_pairs_vec: Vec<(f64, f64)>, answer_vec: &[Vec<(f64, f64)>]) {
         let bd_pairs = bd_pairs_vec
@@ -96,11 +348,18 @@ mod tests {
         //     })
         //     .collect();
 
-        let filtered_pairs = fast_pl::barcode::filter(bd_pairs, k);
-        let landscape = fast_pl::persistencelandscape::generate(filtered_pairs, k, false);
+        let filtered_pairs = fast_pl::barcode::filter(bd_pairs, k).unwrap();
+        let landscape = fast_pl::persistencelandscape::generate(filtered_pairs, k, false).unwrap();
         assert!(answer_vec == landscape);
     }
 
+    fn to_pairs(pairs: &[(f64, f64)]) -> Vec<fast_pl::birthdeath::BirthDeath> {
+        pairs
+            .iter()
+            .map(|&(x, y)| fast_pl::birthdeath::BirthDeath { birth: x, death: y })
+            .collect()
+    }
+
     #[test]
     fn basic_triple() {
         let k = 4;
@@ -203,7 +462,13 @@ mod tests {
                 (0.8078512625210992,0.0),
             ],
             ];
-            test_runner(k, bd_pairs_vec, &answer_vec);
+        // The expected vertices came from a general line intersection, which is a few ulps off the
+        // correctly rounded midpoints the sweep computes now
+        let bd_pairs = to_pairs(&bd_pairs_vec);
+        let filtered_pairs = fast_pl::barcode::filter(bd_pairs, k).unwrap();
+        let landscape = fast_pl::persistencelandscape::generate(filtered_pairs, k, false).unwrap();
+        let diff = fast_pl::compare::compare(&answer_vec, &landscape, fast_pl::tolerance::Tolerance::Ulps(4));
+        assert!(diff.is_empty(), "{diff}");
     }
     #[test]
     fn same_start() {
@@ -320,5 +585,829 @@ mod tests {
                 "This test only works with f64. create new test for condition if type changes");
             test_runner(k, bd_pairs_vec, &answer_vec);
     }
+    #[test]
+    fn accumulator_mean() {
+        let mut sequential = fast_pl::accumulator::LandscapeAccumulator::new(1);
+        let mut first = fast_pl::accumulator::LandscapeAccumulator::new(1);
+        let mut second = fast_pl::accumulator::LandscapeAccumulator::new(1);
+        for (acc, pairs) in [
+            (&mut first, vec![(1.0, 3.0), (3.0, 5.0)]),
+            (&mut second, vec![(1.0, 3.0)]),
+        ] {
+            acc.add_pairs(to_pairs(&pairs), false, false, fast_pl::tolerance::Tolerance::default()).unwrap();
+            sequential.add_pairs(to_pairs(&pairs), false, false, fast_pl::tolerance::Tolerance::default()).unwrap();
+        }
+        first.merge(&second);
+        let answer_vec = vec![vec![
+            (1.0, 0.0),
+            (2.0, 1.0),
+            (3.0, 0.0),
+            (4.0, 0.5),
+            (5.0, 0.0),
+        ]];
+        assert_eq!(sequential.count(), 2);
+        assert!(answer_vec == sequential.mean());
+        assert!(first == sequential);
+    }
+    #[test]
+    fn pointwise_statistics() {
+        let landscapes = vec![
+            vec![vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]],
+            vec![vec![(1.0, 0.0), (2.0, 1.0), (3.0, 0.0)]],
+        ];
+        let max_answer = vec![vec![(0.0, 0.0), (1.0, 1.0), (1.5, 0.5), (2.0, 1.0), (3.0, 0.0)]];
+        let min_answer = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.5, 0.5), (2.0, 0.0), (3.0, 0.0)]];
+        let median_answer = vec![vec![(0.0, 0.0), (1.0, 0.5), (1.5, 0.5), (2.0, 0.5), (3.0, 0.0)]];
+        assert!(max_answer == fast_pl::pointwise::max_envelope(&landscapes));
+        assert!(min_answer == fast_pl::pointwise::min_envelope(&landscapes));
+        assert!(median_answer == fast_pl::pointwise::median(&landscapes));
+        assert!(max_answer == fast_pl::pointwise::quantile(&landscapes, 1.0).unwrap());
+        assert!(median_answer == fast_pl::pointwise::trimmed_mean(&landscapes, 0.0).unwrap());
+    }
+    #[test]
+    fn landscape_lp_distance() {
+        let landscape = vec![vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)], vec![]];
+        let shifted = vec![vec![(1.0, 0.0), (2.0, 1.0), (3.0, 0.0)]];
+        assert!((fast_pl::piecewiselinear::lp_norm(&landscape, 1.0).unwrap() - 1.0).abs() < 1e-12);
+        assert!((fast_pl::piecewiselinear::lp_norm(&landscape, 2.0).unwrap() - (2.0_f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!((fast_pl::piecewiselinear::lp_distance(&landscape, &shifted, 1.0).unwrap() - 1.5).abs() < 1e-12);
+        assert!((fast_pl::piecewiselinear::lp_distance(&landscape, &shifted, f64::INFINITY).unwrap() - 1.0).abs() < 1e-12);
+    }
+    #[test]
+    fn permutation_test_seeded() {
+        let group_a = vec![
+            vec![vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]],
+            vec![vec![(0.0, 0.0), (1.5, 1.5), (3.0, 0.0)]],
+            vec![vec![(0.0, 0.0), (1.2, 1.2), (2.4, 0.0)]],
+        ];
+        let group_b = vec![
+            vec![vec![(5.0, 0.0), (5.5, 0.5), (6.0, 0.0)]],
+            vec![vec![(5.0, 0.0), (5.25, 0.25), (5.5, 0.0)]],
+            vec![vec![(4.0, 0.0), (4.5, 0.5), (5.0, 0.0)]],
+        ];
+        let first = fast_pl::permutation::permutation_test(&group_a, &group_b, 2.0, 200, 7).unwrap();
+        let second = fast_pl::permutation::permutation_test(&group_a, &group_b, 2.0, 200, 7).unwrap();
+        assert!(first == second);
+        assert!(first.statistic > 0.0);
+        assert!(first.p_value < 0.25);
+        let same = fast_pl::permutation::permutation_test(&group_a, &group_a, 2.0, 50, 7).unwrap();
+        assert!(same.statistic.abs() < f64::EPSILON);
+        assert!((same.p_value - 1.0).abs() < f64::EPSILON);
+    }
+    #[test]
+    fn bootstrap_band_seeded() {
+        let tent = vec![vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]];
+        let same = fast_pl::bootstrap::bootstrap_band(&[tent.clone(), tent.clone()], 0.9, 50, 3).unwrap();
+        assert!(same.half_width.abs() < f64::EPSILON);
+        assert!(same.lower == same.mean && same.upper == same.mean);
+
+        let landscapes = vec![tent, vec![vec![(0.0, 0.0), (2.0, 2.0), (4.0, 0.0)]]];
+        let first = fast_pl::bootstrap::bootstrap_band(&landscapes, 0.9, 100, 3).unwrap();
+        let second = fast_pl::bootstrap::bootstrap_band(&landscapes, 0.9, 100, 3).unwrap();
+        assert!(first == second);
+        // Resampling one diagram twice moves the mean by half their sup distance
+        assert!((first.half_width - 1.0).abs() < 1e-12);
+        assert!(first.upper[0][1] == (1.0, 2.0));
+        assert!(first.to_plot().len() == 3);
+    }
+    #[test]
+    fn landscape_gram_matrix() {
+        use fast_pl::kernel::{Kernel, LandscapeKernel};
+        let landscapes = vec![
+            vec![vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]],
+            vec![vec![(1.0, 0.0), (2.0, 1.0), (3.0, 0.0)]],
+            vec![vec![(0.0, 0.0), (2.0, 2.0), (4.0, 0.0)], vec![(1.0, 0.0), (1.5, 0.5), (2.0, 0.0)]],
+        ];
+        let gram = fast_pl::kernel::gram_matrix(&landscapes, &LandscapeKernel::Linear).unwrap();
+        assert!((gram[0][0] - 2.0 / 3.0).abs() < 1e-12);
+        assert!((gram[0][1] - 1.0 / 6.0).abs() < 1e-12);
+        assert!((gram[2][2] - (16.0 / 3.0 + 1.0 / 12.0)).abs() < 1e-12);
+        assert!(gram[1][2].to_bits() == gram[2][1].to_bits());
+        let gaussian = LandscapeKernel::Gaussian { sigma: 1.0, p: 2.0 };
+        assert!((gaussian.evaluate(&landscapes[2], &landscapes[2]) - 1.0).abs() < f64::EPSILON);
+        for invalid in [
+            LandscapeKernel::Gaussian { sigma: 1.0, p: 0.5 },
+            LandscapeKernel::Gaussian { sigma: 0.0, p: 2.0 },
+            LandscapeKernel::Gaussian { sigma: f64::NAN, p: 2.0 },
+        ] {
+            assert!(matches!(
+                fast_pl::kernel::gram_matrix(&landscapes, &invalid),
+                Err(fast_pl::error::Error::InvalidArgument(_))
+            ));
+        }
+
+        // Named by process so concurrent test runs do not remove each other's files
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let npy = dir.join(format!("fast_pl_gram_test_{id}.npy")).to_string_lossy().to_string();
+        let csv = dir.join(format!("fast_pl_gram_test_{id}.csv")).to_string_lossy().to_string();
+        let kernel = LandscapeKernel::Linear;
+        fast_pl::kernel::write_gram_matrix(&npy, &landscapes, &kernel, 2, fast_pl::fileio::MatrixFormat::Npy).unwrap();
+        fast_pl::kernel::write_gram_matrix(&csv, &landscapes, &kernel, 2, fast_pl::fileio::MatrixFormat::Csv).unwrap();
+        let bytes = std::fs::read(&npy).unwrap();
+        assert!(bytes.len() == 128 + 9 * 8);
+        let from_npy: Vec<f64> = bytes[128..]
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        let from_csv: Vec<f64> = std::fs::read_to_string(&csv)
+            .unwrap()
+            .lines()
+            .flat_map(|l| l.split(',').map(|v| v.parse::<f64>().unwrap()).collect::<Vec<_>>())
+            .collect();
+        let expected: Vec<f64> = gram.into_iter().flatten().collect();
+        assert!(from_npy == expected);
+        assert!(from_csv == expected);
+        std::fs::remove_file(npy).unwrap();
+        std::fs::remove_file(csv).unwrap();
+    }
+    #[test]
+    fn power_weighted_silhouette() {
+        let silhouette = fast_pl::rpls::pairs_to_silhouette(to_pairs(&[(0.0, 2.0), (1.0, 3.0)]), 1.0, false, fast_pl::tolerance::Tolerance::default()).unwrap();
+        assert!(silhouette == vec![(0.0, 0.0), (1.0, 0.5), (2.0, 0.5), (3.0, 0.0)]);
+
+        let silhouette = fast_pl::rpls::pairs_to_silhouette(to_pairs(&[(0.0, 4.0), (1.0, 3.0)]), 1.0, false, fast_pl::tolerance::Tolerance::default()).unwrap();
+        let answer_vec = [(0.0, 0.0), (1.0, 2.0 / 3.0), (2.0, 5.0 / 3.0), (3.0, 2.0 / 3.0), (4.0, 0.0)];
+        assert!(silhouette.len() == answer_vec.len());
+        for (a, b) in silhouette.iter().zip(answer_vec) {
+            assert!((a.0 - b.0).abs() < 1e-12 && (a.1 - b.1).abs() < 1e-12);
+        }
+        assert!(fast_pl::rpls::pairs_to_silhouette(to_pairs(&[(1.0, 1.0)]), 1.0, false, fast_pl::tolerance::Tolerance::default()).is_err());
+    }
+    #[test]
+    fn betti_and_euler_curves() {
+        let dim0 = to_pairs(&[(0.0, 2.0), (1.0, 3.0), (1.0, f64::INFINITY), (2.0, 2.0)]);
+        let dim1 = to_pairs(&[(1.5, 2.5)]);
+        let betti = fast_pl::betticurve::betti_curve(&dim0);
+        assert!(betti == vec![(0.0, 1), (1.0, 3), (2.0, 2), (3.0, 1)]);
+        let grid = fast_pl::stepfunction::linspace(-1.0, 3.0, 5);
+        assert!(fast_pl::stepfunction::sample(&betti, &grid) == vec![0, 1, 3, 2, 1]);
+
+        let euler = fast_pl::betticurve::euler_characteristic_curve(&[dim0, dim1]);
+        assert!(euler == vec![(0.0, 1), (1.0, 3), (1.5, 2), (2.0, 1), (2.5, 2), (3.0, 1)]);
+    }
+    #[test]
+    fn persistence_image_mass() {
+        use fast_pl::persistenceimage::{PersistenceImageConfig, Weighting};
+        let pairs = || vec![fast_pl::birthdeath::BirthDeath { birth: 0.0, death: 1.0 }];
+        let config = PersistenceImageConfig {
+            sigma: 0.5,
+            resolution: (2, 1),
+            bounds: Some(((-10.0, 10.0), (-10.0, 12.0))),
+            weighting: Weighting::Linear,
+        };
+        let image = fast_pl::persistenceimage::generate(pairs(), &config).unwrap();
+        assert!(image.len() == 1 && image[0].len() == 2);
+        assert!((image[0][0] - 0.5).abs() < 1e-6);
+        assert!((image[0][0] - image[0][1]).abs() < 1e-6);
+
+        let doubled = PersistenceImageConfig { weighting: Weighting::Custom(|_, _| 2.0), ..config };
+        let image = fast_pl::persistenceimage::generate(pairs(), &doubled).unwrap();
+        assert!((image[0][0] + image[0][1] - 2.0).abs() < 1e-6);
+
+        let image = fast_pl::persistenceimage::generate(pairs(), &PersistenceImageConfig::default()).unwrap();
+        assert!(image.len() == 20 && image.iter().all(|row| row.len() == 20));
+    }
+    #[test]
+    fn diagram_statistics() {
+        let bd_pairs = to_pairs(&[(0.0, 2.0), (1.0, 3.0), (0.0, f64::INFINITY)]);
+        let stats = fast_pl::statistics::summarize(&bd_pairs, 2.0);
+        assert!(stats.count == 3 && stats.finite == 2 && stats.infinite == 1);
+        assert!((stats.persistence_median - 2.0).abs() < f64::EPSILON);
+        assert!((stats.total_persistence - 8.0).abs() < f64::EPSILON);
+        assert!((stats.entropy - std::f64::consts::LN_2).abs() < 1e-12);
+        assert!((stats.death_max - 3.0).abs() < f64::EPSILON);
+        assert!(stats.to_json().starts_with("{\"count\": 3, \"finite\": 2, \"infinite\": 1,"));
+
+        let empty = fast_pl::statistics::summarize(&[], 1.0);
+        assert!(empty.persistence_mean.is_nan());
+        assert!(empty.to_json().contains("\"entropy\": null"));
+    }
+    #[test]
+    fn bottleneck_matching() {
+        use fast_pl::bottleneck::Matched;
+        let a = to_pairs(&[(0.0, 4.0), (1.0, 1.2), (0.0, f64::INFINITY)]);
+        let b = to_pairs(&[(0.0, 4.5), (0.25, f64::INFINITY)]);
+        let matching = fast_pl::bottleneck::bottleneck(&a, &b).unwrap();
+        assert!((matching.distance - 0.5).abs() < f64::EPSILON);
+        assert!(matching.pairs.contains(&(Matched::Pair(0), Matched::Pair(0))));
+        assert!(matching.pairs.contains(&(Matched::Pair(1), Matched::Diagonal)));
+        assert!(matching.pairs.contains(&(Matched::Pair(2), Matched::Pair(1))));
+        assert!(matching.pairs.len() == 3);
+
+        let far = to_pairs(&[(10.0, 11.0)]);
+        assert!((fast_pl::bottleneck::bottleneck_distance(&a[..2], &far).unwrap() - 2.0).abs() < f64::EPSILON);
+        assert!(fast_pl::bottleneck::bottleneck_distance(&a, &far).unwrap().is_infinite());
+        assert!(fast_pl::bottleneck::bottleneck_distance(&[], &[]).unwrap().abs() < f64::EPSILON);
+        for bad in [(f64::NAN, 1.0), (2.0, 1.0)] {
+            assert!(matches!(
+                fast_pl::bottleneck::bottleneck(&a, &to_pairs(&[(0.0, 1.0), bad])),
+                Err(fast_pl::error::Error::InvalidPair { index: 1, .. })
+            ));
+        }
+    }
+    #[test]
+    fn bottleneck_matches_dense_search() {
+        use fast_pl::bottleneck::Matched;
+        use rand::{Rng, SeedableRng};
+        // Smallest pairwise or diagonal distance with a perfect matching, by Kuhn's algorithm on
+        // the whole threshold graph. A point that goes to the diagonal needs its own projection.
+        fn reference(a: &[(f64, f64)], b: &[(f64, f64)]) -> f64 {
+            fn kuhn(u: usize, ok: &dyn Fn(usize, usize) -> bool, seen: &mut [bool], right: &mut [Option<usize>]) -> bool {
+                for v in 0..seen.len() {
+                    if ok(u, v) && !seen[v] {
+                        seen[v] = true;
+                        if right[v].is_none_or(|w| kuhn(w, ok, seen, right)) {
+                            right[v] = Some(u);
+                            return true;
+                        }
+                    }
+                }
+                false
+            }
+            let dist = |p: (f64, f64), q: (f64, f64)| (p.0 - q.0).abs().max((p.1 - q.1).abs());
+            let diag = |p: (f64, f64)| (p.1 - p.0).abs() / 2.0;
+            let (n, m) = (a.len(), b.len());
+            let cost = |u: usize, v: usize| match (u < n, v < m) {
+                (true, true) => dist(a[u], b[v]),
+                (true, false) => if v - m == u { diag(a[u]) } else { f64::INFINITY },
+                (false, true) => if u - n == v { diag(b[v]) } else { f64::INFINITY },
+                (false, false) => 0.0,
+            };
+            let mut candidates: Vec<f64> = (0..n + m).flat_map(|u| (0..n + m).map(move |v| (u, v))).map(|(u, v)| cost(u, v)).collect();
+            candidates.sort_by(f64::total_cmp);
+            candidates.dedup();
+            let index = candidates.partition_point(|&r| {
+                let mut right = vec![None; n + m];
+                !(0..n + m).all(|u| kuhn(u, &|u, v| cost(u, v) <= r, &mut vec![false; n + m], &mut right))
+            });
+            candidates[index]
+        }
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(17);
+        for case in 0..60 {
+            // Small integers give many ties, a common persistence puts every pairwise distance
+            // between the same two diagonal distances and the large offset rounds them
+            let mut random = |k: usize| -> Vec<(f64, f64)> {
+                (0..k)
+                    .map(|_| match case % 3 {
+                        0 => {
+                            let b = f64::from(rng.random_range(0..5));
+                            (b, b + f64::from(rng.random_range(0..4)))
+                        }
+                        1 => {
+                            let b = f64::from(rng.random_range(0..50));
+                            (b, b + 100.0)
+                        }
+                        _ => {
+                            let b = f64::from(rng.random_range(0..50)).mul_add(1e-3, 1e6);
+                            (b, f64::from(rng.random_range(0..9)).mul_add(0.1, b))
+                        }
+                    })
+                    .collect()
+            };
+            let (a, b) = (random(case % 40), random(40 - case % 40));
+            let matching = fast_pl::bottleneck::bottleneck(&to_pairs(&a), &to_pairs(&b)).unwrap();
+            assert!(matching.distance.to_bits() == reference(&a, &b).to_bits());
+            let worst = matching.pairs.iter().map(|pair| match *pair {
+                (Matched::Pair(i), Matched::Pair(j)) => (a[i].0 - b[j].0).abs().max((a[i].1 - b[j].1).abs()),
+                (Matched::Pair(i), Matched::Diagonal) => (a[i].1 - a[i].0) / 2.0,
+                (Matched::Diagonal, Matched::Pair(j)) => (b[j].1 - b[j].0) / 2.0,
+                (Matched::Diagonal, Matched::Diagonal) => f64::INFINITY,
+            });
+            assert!(worst.fold(0.0, f64::max).to_bits() == matching.distance.to_bits());
+            assert!(matching.pairs.len() >= a.len().max(b.len()));
+        }
+    }
+    #[test]
+    fn wasserstein_exact_and_auction() {
+        use rand::{Rng, SeedableRng};
+        let a = to_pairs(&[(0.0, 4.0), (1.0, 1.2), (0.0, f64::INFINITY)]);
+        let b = to_pairs(&[(0.0, 4.5), (0.25, f64::INFINITY)]);
+        assert!((fast_pl::wasserstein::wasserstein_distance(&a, &b, 1.0).unwrap() - 0.85).abs() < 1e-12);
+        assert!((fast_pl::wasserstein::wasserstein_distance(&a[..2], &b[..1], 2.0).unwrap() - 0.26_f64.sqrt()).abs() < 1e-12);
+        let c = to_pairs(&[(0.0, 1.0), (0.3, 0.9)]);
+        let d = to_pairs(&[(0.1, 1.2), (0.4, 0.7)]);
+        assert!(matches!(
+            fast_pl::wasserstein::wasserstein_auction(&c, &d, 2.0, 1e-300),
+            Err(fast_pl::error::Error::NotConverged { achieved, .. }) if achieved > 1e-300
+        ));
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let mut random = |n: usize| -> Vec<fast_pl::birthdeath::BirthDeath> {
+            let pairs: Vec<(f64, f64)> = (0..n)
+                .map(|_| {
+                    let b: f64 = rng.random_range(0.0..1.0);
+                    (b, b + rng.random_range(0.0..0.5))
+                })
+                .collect();
+            to_pairs(&pairs)
+        };
+        let (a, b) = (random(60), random(45));
+        for q in [1.0, 2.0] {
+            let exact = fast_pl::wasserstein::wasserstein_exact(&a, &b, q).unwrap().distance;
+            let approx = fast_pl::wasserstein::wasserstein_auction(&a, &b, q, 0.01).unwrap().distance;
+            assert!(approx >= exact - 1e-9 && approx <= exact.mul_add(1.01, 1e-9));
+            assert!(fast_pl::bottleneck::bottleneck_distance(&a, &b).unwrap() <= exact + 1e-9);
+        }
+    }
+    #[test]
+    fn diagram_kernels() {
+        use fast_pl::kernel::{Kernel, PersistenceScaleSpaceKernel, SlicedWassersteinKernel};
+        let diagrams = vec![
+            to_pairs(&[(0.0, 2.0)]),
+            to_pairs(&[]),
+            to_pairs(&[(0.0, 2.0), (1.0, 3.0), (0.5, f64::INFINITY)]),
+        ];
+        let sliced = SlicedWassersteinKernel { sigma: 1.0, directions: 1000 };
+        let expected = 2.0 * std::f64::consts::SQRT_2 / std::f64::consts::PI;
+        assert!((sliced.distance(&diagrams[0], &diagrams[1]) - expected).abs() < 1e-3);
+        assert!(sliced.distance(&diagrams[2], &diagrams[2]).abs() < 1e-12);
+        assert!((sliced.evaluate(&diagrams[0], &diagrams[0]) - 1.0).abs() < f64::EPSILON);
+
+        let scale_space = PersistenceScaleSpaceKernel { sigma: 1.0 };
+        let expected = (1.0 - (-1.0_f64).exp()) / (8.0 * std::f64::consts::PI);
+        assert!((scale_space.evaluate(&diagrams[0], &diagrams[0]) - expected).abs() < 1e-12);
+        assert!(scale_space.evaluate(&diagrams[0], &diagrams[1]).abs() < f64::EPSILON);
+
+        let gram = fast_pl::kernel::gram_matrix(&diagrams, &scale_space).unwrap();
+        assert!(gram[0][2].to_bits() == gram[2][0].to_bits());
+        let npy = std::env::temp_dir()
+            .join(format!("fast_pl_diagram_gram_test_{}.npy", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        fast_pl::kernel::write_gram_matrix(&npy, &diagrams, &sliced, 2, fast_pl::fileio::MatrixFormat::Npy).unwrap();
+        let from_npy: Vec<f64> = std::fs::read(&npy).unwrap()[128..]
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        let expected: Vec<f64> = fast_pl::kernel::gram_matrix(&diagrams, &sliced).unwrap().into_iter().flatten().collect();
+        assert!(from_npy == expected);
+        std::fs::remove_file(npy).unwrap();
+
+        let invalid = |result: Result<Vec<Vec<f64>>, fast_pl::error::Error>| {
+            matches!(result, Err(fast_pl::error::Error::InvalidArgument(_)))
+        };
+        assert!(invalid(fast_pl::kernel::gram_matrix(&diagrams, &SlicedWassersteinKernel { sigma: 1.0, directions: 0 })));
+        assert!(invalid(fast_pl::kernel::gram_matrix(&diagrams, &SlicedWassersteinKernel { sigma: -1.0, directions: 10 })));
+        assert!(invalid(fast_pl::kernel::gram_matrix(&diagrams, &PersistenceScaleSpaceKernel { sigma: 0.0 })));
+        assert!(invalid(fast_pl::kernel::gram_matrix(&diagrams, &PersistenceScaleSpaceKernel { sigma: f64::NAN })));
+    }
+    #[test]
+    fn lifetime_step_functions() {
+        let bd_pairs = to_pairs(&[(0.0, 2.0), (1.0, 3.0), (2.0, 2.5), (0.0, f64::INFINITY)]);
+        let apf = fast_pl::lifetime::accumulated_persistence(&bd_pairs);
+        assert!(apf == vec![(1.0, 2.0), (2.0, 4.0), (2.25, 4.5)]);
+        let cumulative = fast_pl::lifetime::cumulative_lifetime(&bd_pairs);
+        assert!(cumulative == vec![(2.0, 2.0), (2.5, 2.5), (3.0, 4.5)]);
+        let grid = fast_pl::stepfunction::linspace(0.0, 3.0, 4);
+        assert!(fast_pl::stepfunction::sample(&apf, &grid) == vec![0.0, 2.0, 4.0, 4.5]);
+    }
+    #[test]
+    fn summaries_by_name() {
+        use fast_pl::summary::{Summary, SummaryKind, SummaryOptions};
+        let options = SummaryOptions::default();
+        let landscape = fast_pl::summary::Landscape::compute(to_pairs(&[(0.0, 2.0)]), &options).unwrap();
+        assert!(landscape.evaluate(0.5) == vec![0.5]);
+        assert!((landscape.norm(1.0).unwrap() - 1.0).abs() < 1e-12);
+        let betti = fast_pl::summary::BettiCurve::compute(to_pairs(&[(0.0, 2.0), (1.0, 3.0)]), &options).unwrap();
+        assert!((betti.norm(1.0).unwrap() - 4.0).abs() < 1e-12);
+        assert!(betti.vectorize(&[0.5, 1.5, 2.5]) == vec![1.0, 2.0, 1.0]);
+        let apf = fast_pl::summary::AccumulatedPersistence::compute(to_pairs(&[(0.0, 2.0), (1.0, 3.0)]), &options).unwrap();
+        assert!((apf.norm(1.0).unwrap() - 6.0).abs() < 1e-12);
+        let single = fast_pl::summary::AccumulatedPersistence::compute(to_pairs(&[(0.0, 2.0)]), &options).unwrap();
+        assert!((apf.distance(&single, 1.0).unwrap() - 2.0).abs() < 1e-12);
+        assert!((apf.distance(&single, f64::INFINITY).unwrap() - 2.0).abs() < 1e-12);
+
+        let grid = fast_pl::stepfunction::linspace(0.0, 3.0, 7);
+        for kind in SummaryKind::ALL {
+            assert!(kind.name().parse::<SummaryKind>() == Ok(kind));
+            let vectors = kind
+                .vectorize(vec![to_pairs(&[(0.0, 2.0), (1.0, 3.0)]), to_pairs(&[(1.0, 3.0), (0.0, 2.0)])], &options, &grid)
+                .unwrap();
+            let (a, b) = (&vectors[0], &vectors[1]);
+            assert!(a.len() == b.len() && !a.is_empty());
+            assert!(a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-12));
+            let d = kind.distance(to_pairs(&[(0.0, 2.0)]), to_pairs(&[(0.0, 2.0)]), &options, 2.0).unwrap();
+            assert!(d.abs() < f64::EPSILON);
+        }
+        assert!("bogus".parse::<SummaryKind>().is_err());
+
+        // Images of diagrams with different ranges still cover the same pixels
+        let images = SummaryKind::PersistenceImage
+            .vectorize(vec![to_pairs(&[(0.0, 1.0)]), to_pairs(&[(5.0, 9.0)])], &options, &grid)
+            .unwrap();
+        let bounds = fast_pl::persistenceimage::common_bounds(&[to_pairs(&[(0.0, 1.0), (5.0, 9.0)])], &options.image);
+        let image = fast_pl::persistenceimage::generate(to_pairs(&[(0.0, 1.0)]), &fast_pl::persistenceimage::PersistenceImageConfig {
+            bounds: Some(bounds),
+            ..options.image
+        })
+        .unwrap();
+        assert!(images[0] == image.into_iter().flatten().collect::<Vec<f64>>());
+    }
+    #[test]
+    fn basis_projections() {
+        use fast_pl::basis::{project_level, Basis};
+        let tent = vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)];
+        let legendre = project_level(&tent, Basis::Legendre, (0.0, 2.0), 40).unwrap();
+        assert!((legendre[0] - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-12);
+        assert!(legendre[1].abs() < 1e-12);
+        let energy: f64 = legendre.iter().map(|c| c * c).sum();
+        assert!(energy <= 2.0 / 3.0 + 1e-12 && energy > 2.0 / 3.0 - 1e-4);
+
+        let fourier = project_level(&tent, Basis::Fourier, (0.0, 2.0), 3).unwrap();
+        assert!(fourier.len() == Basis::Fourier.len(3));
+        assert!((fourier[0] - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-12);
+        assert!(fourier.iter().skip(2).step_by(2).all(|c| c.abs() < 1e-12));
+        // cos(pi x) against the tent integrates to -4 / pi^2
+        assert!((fourier[1] + 4.0 / (std::f64::consts::PI * std::f64::consts::PI)).abs() < 1e-12);
+
+        let hats = project_level(&tent, Basis::BSpline { degree: 1 }, (0.0, 2.0), 2).unwrap();
+        assert!(hats.iter().zip([1.0 / 6.0, 2.0 / 3.0, 1.0 / 6.0]).all(|(c, e)| (c - e).abs() < 1e-12));
+        let cubic = project_level(&tent, Basis::BSpline { degree: 3 }, (-1.0, 3.0), 5).unwrap();
+        assert!((cubic.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        let projected = fast_pl::basis::project(&[tent, Vec::new()], Basis::Legendre, (0.0, 2.0), 2).unwrap();
+        assert!(projected[1] == vec![0.0; 3]);
+    }
+    #[test]
+    fn typed_errors() {
+        use fast_pl::error::Error;
+        assert!(matches!(fast_pl::rpls::pairs_to_landscape(Vec::new(), 1, false, false, fast_pl::tolerance::Tolerance::default()), Err(Error::EmptyInput)));
+        let invalid = fast_pl::persistencelandscape::generate(to_pairs(&[(0.0, 1.0), (3.0, 2.0)]), 1, false);
+        assert!(matches!(invalid, Err(Error::InvalidPair { index: 1, .. })));
+        let nan = fast_pl::barcode::filter(to_pairs(&[(f64::NAN, 1.0)]), 1);
+        assert!(matches!(nan, Err(Error::InvalidPair { index: 0, .. })));
+        assert!(matches!(
+            fast_pl::piecewiselinear::lp_norm(&[vec![(0.0, 0.0)]], 0.5),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!("1 x".parse::<fast_pl::birthdeath::BirthDeath>(), Err(Error::Parse { line: 1, .. })));
+
+        let name = format!("fast_pl_parse_error_test_{}.txt", std::process::id());
+        let path = std::env::temp_dir().join(name).to_string_lossy().to_string();
+        std::fs::write(&path, "0 1\n\n2 oops\n").unwrap();
+        let parsed = fast_pl::birthdeath::read_file(&path);
+        assert!(matches!(parsed, Err(Error::Parse { line: 3, .. })));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(fast_pl::birthdeath::read_file(&path), Err(Error::Io(_))));
+    }
+    #[test]
+    fn landscape_area() {
+        let landscapes = vec![vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)], vec![(0.5, 0.0), (1.0, 0.5), (1.5, 0.0)]];
+        assert!((fast_pl::rpls::l2_norm(&landscapes).unwrap() - 1.25).abs() < 1e-12);
+        let swapped = vec![landscapes[1].clone(), landscapes[0].clone()];
+        assert!(fast_pl::rpls::l2_norm(&swapped).is_err());
+        // A flat segment has no height to hide a decreasing x behind
+        let backwards = vec![vec![(0.0, 0.0), (1.0, 1.0), (0.5, 1.0), (2.0, 0.0)]];
+        assert!(matches!(fast_pl::rpls::l2_norm(&backwards), Err(fast_pl::error::Error::InvalidArgument(m)) if m.contains("decreases")));
+    }
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn exact_landscape() {
+        use fast_pl::persistencelandscape::{generate_exact, Coordinate, HalfUnits};
+        let pairs = vec![(1, 8), (3, 7), (4, 9), (4, 10), (6, 7)];
+        let exact = generate_exact(&pairs, 4, false).unwrap();
+        let bd_pairs = pairs
+            .iter()
+            .map(|&(birth, death)| fast_pl::birthdeath::BirthDeath { birth: birth as f64, death: death as f64 })
+            .collect();
+        let float = fast_pl::persistencelandscape::generate(bd_pairs, 4, false).unwrap();
+        let converted: Vec<Vec<(f64, f64)>> = exact
+            .iter()
+            .map(|level| level.iter().map(|&(x, y)| (x.to_f64(), y.to_f64())).collect())
+            .collect();
+        assert_eq!(converted, float);
+        assert_eq!((exact[0][1].0.halves(), exact[0][1].1.halves()), (9, 7));
+        assert_eq!(format!("{}", exact[0][1].0), "4.5");
+        assert_eq!(format!("{}", generate_exact(&[(-1, 0)], 1, false).unwrap()[0][1].0), "-0.5");
+
+        // Past 2^53 a peak at a half integer can not be held by an f64
+        let big = 1i64 << 60;
+        let exact = generate_exact(&[(big, big + 3), (big + 1, big + 2)], 2, false).unwrap();
+        let halves = |level: &[(HalfUnits, HalfUnits)]| -> Vec<(i128, i128)> {
+            level.iter().map(|(x, y)| (x.halves(), y.halves())).collect()
+        };
+        let start = 2 * i128::from(big);
+        assert_eq!(halves(&exact[0]), vec![(start, 0), (start + 3, 3), (start + 6, 0)]);
+        assert_eq!(halves(&exact[1])[1], (start + 3, 1));
+        assert!(generate_exact(&[(2, 1)], 1, false).is_err());
+    }
+    #[test]
+    fn tolerance_policies() {
+        use fast_pl::tolerance::Tolerance;
+        let up = |x: f64| f64::from_bits(x.to_bits() + 1);
+        assert!(Tolerance::default().approx_eq(0.5, up(0.5)));
+        assert!(!Tolerance::default().approx_eq(1e6, up(1e6)));
+        assert!(Tolerance::Relative(1e-12).approx_eq(1e6, 1e6 + 1e-9));
+        assert!(!Tolerance::Relative(1e-12).approx_eq(1e-9, 2e-9));
+        assert!(Tolerance::Ulps(1).approx_eq(-0.0, up(0.0)));
+        assert!(Tolerance::Ulps(2).approx_eq(1e6, up(up(1e6))));
+        assert!(!Tolerance::Ulps(2).approx_eq(1.0, up(up(up(1.0)))));
+        assert!(!Tolerance::Relative(1.0).approx_eq(1.0, f64::INFINITY));
+        assert!(Tolerance::EXACT.approx_eq(0.5, 0.5) && !Tolerance::EXACT.approx_eq(0.5, up(0.5)));
+        // Scaled tolerances do not reach past zero
+        assert!(!Tolerance::Relative(0.5).approx_eq(0.0, 1e-300));
+        assert!(!Tolerance::Ulps(1 << 20).approx_eq(0.0, f64::MIN_POSITIVE));
+
+        for tolerance in [Tolerance::Absolute(1e-9), Tolerance::Relative(0.25), Tolerance::Ulps(4)] {
+            assert_eq!(tolerance.to_string().parse::<Tolerance>(), Ok(tolerance));
+        }
+        assert!("abs:-1".parse::<Tolerance>().is_err());
+        assert!("ulps:1".parse::<Tolerance>().is_err());
+
+        let pairs = || vec![fast_pl::birthdeath::BirthDeath { birth: 1e6, death: 1e6 + 1e-9 }];
+        assert!(fast_pl::rpls::pairs_to_landscape(pairs(), 1, false, false, Tolerance::default()).is_ok());
+        assert!(matches!(
+            fast_pl::rpls::pairs_to_landscape(pairs(), 1, false, false, Tolerance::Relative(1e-12)),
+            Err(fast_pl::error::Error::EmptyInput)
+        ));
+    }
+    #[test]
+    fn event_order_ignores_input_order() {
+        // Shared births, shared deaths, shared peaks and a repeated pair
+        let pairs = [(0.0, 4.0), (0.0, 2.0), (1.0, 4.0), (1.0, 3.0), (2.0, 4.0), (0.0, 4.0), (1.5, 2.5)];
+        let expected = fast_pl::persistencelandscape::generate(to_pairs(&pairs), 7, false).unwrap();
+        let mut order: Vec<usize> = (0..pairs.len()).collect();
+        for seed in 0..200u64 {
+            // Deterministic shuffle
+            let mut state = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            for i in (1..order.len()).rev() {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+                let j = usize::try_from(state >> 33).unwrap() % (i + 1);
+                order.swap(i, j);
+            }
+            let permuted: Vec<(f64, f64)> = order.iter().map(|&i| pairs[i]).collect();
+            let landscape = fast_pl::persistencelandscape::generate(to_pairs(&permuted), 7, false).unwrap();
+            let bits = |l: &[Vec<(f64, f64)>]| -> Vec<Vec<(u64, u64)>> {
+                l.iter().map(|level| level.iter().map(|p| (p.0.to_bits(), p.1.to_bits())).collect()).collect()
+            };
+            assert_eq!(bits(&landscape), bits(&expected), "order {order:?}");
+        }
+    }
+    #[test]
+    fn degenerate_tents() {
+        let generate = |v: &[(f64, f64)]| fast_pl::persistencelandscape::generate(to_pairs(v), 4, false).unwrap();
+
+        // Exact duplicates fill one level each
+        let duplicate = vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)];
+        assert_eq!(generate(&[(0.0, 2.0), (0.0, 2.0)]), vec![duplicate.clone(), duplicate, vec![], vec![]]);
+        // Shared death, the falling segments overlap from the lower peak on
+        assert_eq!(generate(&[(0.0, 4.0), (2.0, 4.0)]), vec![
+            vec![(0.0, 0.0), (2.0, 2.0), (3.0, 1.0), (4.0, 0.0)],
+            vec![(2.0, 0.0), (3.0, 1.0), (4.0, 0.0)],
+            vec![],
+            vec![],
+        ]);
+        // Zero persistence pairs have no tent
+        assert_eq!(generate(&[(2.0, 2.0)]), vec![Vec::<(f64, f64)>::new(); 4]);
+        assert_eq!(generate(&[(1.0, 3.0), (2.0, 2.0), (3.0, 3.0)]), generate(&[(1.0, 3.0)]));
+
+        // Every level is the kth largest tent wherever it is evaluated
+        for pairs in [
+            vec![(0.0, 4.0), (0.0, 2.0), (1.0, 4.0), (1.0, 1.0)],
+            vec![(0.0, 4.0), (1.0, 4.0), (2.0, 4.0), (3.0, 4.0)],
+            vec![(0.0, 4.0), (0.0, 3.0), (0.0, 2.0), (0.0, 4.0)],
+            vec![(1.0, 3.0), (1.0, 3.0), (1.0, 3.0), (0.0, 4.0), (2.0, 2.0)],
+        ] {
+            let landscape = generate(&pairs);
+            for i in 0..=16 {
+                let t = f64::from(i) / 4.0;
+                let mut tents: Vec<f64> = pairs.iter().map(|&(b, d)| (t - b).min(d - t).max(0.0)).collect();
+                tents.sort_by(|a, b| b.total_cmp(a));
+                for (k, level) in landscape.iter().enumerate() {
+                    let expected = tents.get(k).copied().unwrap_or(0.0);
+                    assert!((fast_pl::piecewiselinear::evaluate(level, t) - expected).abs() < 1e-12, "{pairs:?} level {k} at {t}");
+                }
+            }
+        }
+        assert!(fast_pl::persistencelandscape::generate_exact(&[(6, 6), (1, 3)], 1, false).is_ok());
+    }
+    #[test]
+    fn landscape_validation() {
+        use fast_pl::tolerance::Tolerance;
+        use fast_pl::validate::{validate, Violation};
+        let bd_pairs = [(0.0, 4.0), (1.0, 3.0), (2.0, 6.0), (2.0, 6.0), (0.25, 0.5)]
+            .into_iter()
+            .map(|(birth, death)| fast_pl::birthdeath::BirthDeath { birth, death })
+            .collect();
+        let landscape = fast_pl::persistencelandscape::generate(bd_pairs, 5, false).unwrap();
+        assert_eq!(validate(&landscape, Tolerance::default()), vec![]);
+
+        let path = std::env::temp_dir().join(format!("fast_pl_validate_test_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        fast_pl::fileio::write_landscape_csv(path, &landscape).unwrap();
+        assert_eq!(fast_pl::fileio::read_landscape_csv(path).unwrap(), landscape);
+        std::fs::remove_file(path).unwrap();
+
+        let broken = vec![
+            vec![(0.0, 0.0), (1.0, 1.0), (1.0, 0.5), (2.0, 0.0)],
+            vec![(0.0, 0.0), (1.0, 2.0), (2.0, -1e-3)],
+        ];
+        assert_eq!(validate(&broken, Tolerance::default()), vec![
+            Violation::NotIncreasing { level: 0, index: 2, x: 1.0, previous_x: 1.0 },
+            Violation::NotLipschitz { level: 1, index: 1, slope: 2.0 },
+            Violation::Negative { level: 1, index: 2, y: -1e-3 },
+            Violation::NotLipschitz { level: 1, index: 2, slope: -2.001 },
+            Violation::NotCompact { level: 1, index: 2, y: -1e-3 },
+            Violation::NotOrdered { level: 0, x: 1.0, value: 1.0, next_value: 2.0 },
+        ]);
+        // Within the tolerance only the steep and unordered parts remain
+        assert_eq!(validate(&broken[1..], Tolerance::Absolute(1e-2)).len(), 2);
+    }
+    #[test]
+    fn filter_keeps_later_pairs() {
+        // (2, 3) is the top level once (0, 1) has died, (3, 4) is always below (2.5, 6)
+        let pairs = [(0.0, 1.0), (2.0, 3.0), (2.5, 6.0), (3.0, 4.0)];
+        let mut kept: Vec<(f64, f64)> = fast_pl::barcode::filter(to_pairs(&pairs), 1)
+            .unwrap()
+            .iter()
+            .map(|bd| (bd.birth, bd.death))
+            .collect();
+        kept.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(kept, [(0.0, 1.0), (2.0, 3.0), (2.5, 6.0)]);
+    }
+    #[test]
+    fn peaks_rounded_together() {
+        use fast_pl::tolerance::Tolerance;
+        // Both peaks round to x = 5 but the shorter tent really turns first, and the two cross
+        let longer = f64::from_bits(6.0_f64.to_bits() + 1);
+        let landscape = fast_pl::persistencelandscape::generate_with_tolerance(
+            to_pairs(&[(4.0, longer), (4.0, 6.0)]),
+            2,
+            false,
+            Tolerance::Ulps(4),
+        )
+        .unwrap();
+        assert_eq!(landscape, [vec![(4.0, 0.0), (5.0, 1.0), (longer, 0.0)], vec![(4.0, 0.0), (5.0, 1.0), (6.0, 0.0)]]);
+    }
+    #[test]
+    fn tents_thinner_than_rounding() {
+        use fast_pl::tolerance::Tolerance;
+        let up = |x: f64| f64::from_bits(x.to_bits() + 1);
+        // The peak of the first rounds onto its birth and the peak of the second onto its death
+        let thin = [(1.0, up(1.0)), (up(1.0), up(up(1.0)))];
+        for tolerance in [Tolerance::default(), Tolerance::Ulps(4)] {
+            for pairs in [vec![thin[0]], vec![thin[1]], vec![(0.0, 2.0), thin[0], thin[1]]] {
+                let big = pairs.len() > 1;
+                let landscape =
+                    fast_pl::persistencelandscape::generate_with_tolerance(to_pairs(&pairs), 2, false, tolerance).unwrap();
+                let expected = if big { vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)] } else { Vec::new() };
+                assert_eq!(landscape, [expected, Vec::new()]);
+            }
+        }
+    }
+    #[test]
+    fn sweep_matches_reference() {
+        use rand::{Rng, SeedableRng};
+        use fast_pl::tolerance::Tolerance;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(49);
+        for case in 0..1000 {
+            let n = rng.random_range(1..24);
+            let pairs: Vec<(f64, f64)> = match case % 5 {
+                // Uniform
+                0 => (0..n)
+                    .map(|_| {
+                        let birth: f64 = rng.random();
+                        (birth, birth + rng.random::<f64>())
+                    })
+                    .collect(),
+                // Clustered around a few points far from zero
+                1 => {
+                    let centers: Vec<f64> = (0..3).map(|_| rng.random_range(1e3..1e6)).collect();
+                    (0..n)
+                        .map(|_| {
+                            let center = centers[rng.random_range(0..3)];
+                            let birth = rng.random_range(-1e-6..1e-6) + center;
+                            (birth, birth + rng.random_range(0.0..1e-5))
+                        })
+                        .collect()
+                }
+                // Ties, duplicates, zero persistence and ulp sized gaps
+                2 => (0..n)
+                    .map(|_| {
+                        let birth = f64::from(rng.random_range(0..5u8));
+                        let death = birth + f64::from(rng.random_range(0..4u8));
+                        let birth = f64::from_bits(birth.to_bits() + rng.random_range(0..2));
+                        (birth, f64::from_bits(death.to_bits() + rng.random_range(0..2)).max(birth))
+                    })
+                    .collect(),
+                // Near ties far from zero, every midpoint is rounded
+                3 => (0..n)
+                    .map(|_| {
+                        let at = |steps: u8| f64::from(steps).mul_add(1e-3, 1e6);
+                        let birth = at(rng.random_range(0..10));
+                        let death = at(rng.random_range(0..10)).max(birth);
+                        (birth, f64::from_bits(death.to_bits() + rng.random_range(0..2)))
+                    })
+                    .collect(),
+                // Decimals that are not exact in binary
+                _ => (0..n)
+                    .map(|_| {
+                        let birth = f64::from(rng.random_range(0..20u8)) * 0.1;
+                        (birth, f64::from(rng.random_range(1..10u8)).mul_add(0.1, birth))
+                    })
+                    .collect(),
+            };
+            let k = rng.random_range(1..6);
+            let scale = pairs.iter().map(|p| p.1.abs()).fold(1.0, f64::max);
+            let expected = fast_pl::reference::generate(&to_pairs(&pairs), k).unwrap();
+            for tolerance in [
+                Tolerance::default(),
+                Tolerance::Absolute(4.0 * f64::EPSILON * scale),
+                Tolerance::Relative(1e-12),
+                Tolerance::Ulps(4),
+                Tolerance::EXACT,
+            ] {
+                let sweep = |bd_pairs| {
+                    fast_pl::persistencelandscape::generate_with_tolerance(bd_pairs, k, false, tolerance)
+                        .unwrap_or_else(|e| panic!("{pairs:?} with k {k} and {tolerance:?}: {e}"))
+                };
+                let unfiltered = sweep(to_pairs(&pairs));
+                let filtered = sweep(fast_pl::barcode::filter(to_pairs(&pairs), k).unwrap());
+                for landscape in [unfiltered, filtered] {
+                    let diff = fast_pl::compare::compare(&landscape, &expected, Tolerance::Absolute(1e-12 * scale));
+                    assert!(diff.is_empty(), "{pairs:?} with {tolerance:?}: {diff}");
+                }
+            }
+        }
+    }
+    #[test]
+    fn landscape_comparison() {
+        use fast_pl::compare::{compare, LevelDiff};
+        use fast_pl::tolerance::Tolerance;
+        let a = vec![vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)], vec![(0.5, 0.0), (1.0, 0.5), (1.5, 0.0)]];
+        // Same functions with an extra collinear vertex and an ulp of noise
+        let b = vec![
+            vec![(0.0, 0.0), (0.5, 0.5), (1.0, 1.0 + f64::EPSILON), (2.0, 0.0)],
+            vec![(0.5, 0.0), (1.0, 0.5), (1.5, 0.0)],
+        ];
+        assert!(compare(&a, &b, Tolerance::Ulps(1)).is_empty());
+        assert!(!compare(&a, &b, Tolerance::EXACT).is_empty());
+
+        // A lower peak on level 1 and a level missing from one side
+        let c = vec![
+            vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)],
+            vec![(0.5, 0.0), (1.0, 0.25), (1.5, 0.0)],
+            vec![(3.0, 0.0), (3.1, 0.1), (3.2, 0.0)],
+        ];
+        let diff = compare(&a, &c, Tolerance::Absolute(1e-9));
+        assert_eq!(diff.levels.len(), 2);
+        assert_eq!(diff.worst(), Some(&LevelDiff { level: 1, x: 1.0, left: 0.5, right: 0.25 }));
+        assert_eq!(diff.levels[1].level, 2);
+        assert!((diff.levels[1].deviation() - 0.1).abs() < 1e-12);
+        assert!(compare(&a, &c, Tolerance::Absolute(0.3)).is_empty());
+
+        // A relative tolerance fails on the small values, the report is still the sup distance
+        let high = vec![vec![(0.0, 0.0), (1.0, 100.0), (2.0, 0.01), (3.0, 0.0)]];
+        let higher = vec![vec![(0.0, 0.0), (1.0, 100.5), (2.0, 0.02), (3.0, 0.0)]];
+        let diff = compare(&high, &higher, Tolerance::Relative(0.01));
+        assert_eq!(diff.worst(), Some(&LevelDiff { level: 0, x: 1.0, left: 100.0, right: 100.5 }));
+    }
+    #[test]
+    fn nearly_touching_tents() {
+        let up = |x: f64| f64::from_bits(x.to_bits() + 1);
+        for pairs in [
+            vec![(0.0, 1.0), (up(1.0), 2.0)],
+            vec![(0.0, 1.0), (1.0 - f64::EPSILON, 2.0)],
+            vec![(0.1, 0.3), (0.3, 0.5), (0.2, up(0.4))],
+            vec![(0.1, 0.7), (0.1 + f64::EPSILON, 0.7 - f64::EPSILON)],
+        ] {
+            let landscape = fast_pl::persistencelandscape::generate(to_pairs(&pairs), 3, false).unwrap();
+            for level in &landscape {
+                assert!(level.windows(2).all(|w| w[0].0 < w[1].0));
+                assert!(level.iter().all(|p| p.1 >= 0.0));
+            }
+        }
+    }
+    #[test]
+    fn crossings_rounded_onto_vertices() {
+        use fast_pl::tolerance::Tolerance;
+        // Far from zero crossings of different pairs round to the x of a vertex already on the
+        // level, or one step behind it
+        let pairs = [
+            (1000000.006, 1000000.006),
+            (1000000.003, 1000000.006),
+            (1000000.004, 1000000.0059999999),
+            (1000000.001, 1000000.003),
+            (1000000.001, 1000000.0040000001),
+        ];
+        // Two vertices at the same x whose heights only differ by rounding
+        let same_x = [
+            (1000000.004, 1000000.005),
+            (1000000.006, 1000000.006),
+            (1000000.005, 1000000.009),
+            (1000000.003, 1000000.003),
+            (1000000.006, 1000000.0090000001),
+        ];
+        for pairs in [&pairs, &same_x] {
+            let expected = fast_pl::reference::generate(&to_pairs(pairs), 4).unwrap();
+            for tolerance in [Tolerance::default(), Tolerance::Relative(1e-12), Tolerance::Ulps(4), Tolerance::EXACT] {
+                let landscape = fast_pl::rpls::pairs_to_landscape(to_pairs(pairs), 4, false, false, tolerance)
+                    .unwrap_or_else(|e| panic!("{pairs:?} with {tolerance:?}: {e}"));
+                let diff = fast_pl::compare::compare(&landscape, &expected, Tolerance::Absolute(1e-6));
+                assert!(diff.is_empty(), "{pairs:?} with {tolerance:?}: {diff}");
+            }
+        }
+    }
 
 }
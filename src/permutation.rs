@@ -0,0 +1,110 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use crate::accumulator::LandscapeAccumulator;
+use crate::birthdeath::BirthDeath;
+use crate::error::Error;
+use crate::piecewiselinear;
+use crate::rpls;
+use crate::tolerance::Tolerance;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermutationTest {
+    /// Lp distance between the mean landscapes of the two groups
+    pub statistic: f64,
+    /// Fraction of relabellings (counting the observed one) with a statistic at least as large
+    pub p_value: f64,
+    pub permutations: usize,
+}
+
+fn mean_of(landscapes: &[Vec<Vec<(f64,f64)>>], indices: &[usize], k: usize) -> Vec<Vec<(f64,f64)>> {
+    let mut acc = LandscapeAccumulator::new(k);
+    for &i in indices {
+        acc.add(&landscapes[i]);
+    }
+    acc.mean()
+}
+
+fn group_distance(landscapes: &[Vec<Vec<(f64,f64)>>], labels: &[usize], split: usize, k: usize, p: f64) -> f64 {
+    let (a, b) = labels.split_at(split);
+    piecewiselinear::lp_distance_unchecked(&mean_of(landscapes, a, k), &mean_of(landscapes, b, k), p)
+}
+
+/// Two sample permutation test on the Lp distance between mean landscapes. Each relabelling is
+/// drawn from its own generator seeded from `seed`, so the result does not depend on the number of
+/// threads.
+///
+/// # Errors
+///
+/// Will return 'Err' if either group is empty or `p` is less than 1
+pub fn permutation_test(
+    group_a: &[Vec<Vec<(f64,f64)>>],
+    group_b: &[Vec<Vec<(f64,f64)>>],
+    p: f64,
+    permutations: usize,
+    seed: u64,
+) -> Result<PermutationTest, Error> {
+    if group_a.is_empty() || group_b.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    piecewiselinear::check_order(p)?;
+    let k = group_a.iter().chain(group_b).map(Vec::len).max().unwrap_or(0);
+    let landscapes: Vec<Vec<Vec<(f64,f64)>>> = group_a.iter().chain(group_b).cloned().collect();
+    let labels: Vec<usize> = (0..landscapes.len()).collect();
+    let split = group_a.len();
+
+    let statistic = group_distance(&landscapes, &labels, split, k, p);
+    let at_least = (0..permutations)
+        .into_par_iter()
+        .filter(|&i| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+            let mut shuffled = labels.clone();
+            shuffled.shuffle(&mut rng);
+            group_distance(&landscapes, &shuffled, split, k, p) >= statistic
+        })
+        .count();
+
+    #[allow(clippy::cast_precision_loss)]
+    let p_value = (at_least + 1) as f64 / (permutations + 1) as f64;
+    Ok(PermutationTest {
+        statistic,
+        p_value,
+        permutations,
+    })
+}
+
+/// Compute the landscapes of two groups of diagrams and run `permutation_test` on them
+///
+/// # Errors
+///
+/// Will return 'Err' if failed to compute persistencelandscape from any diagram
+#[allow(clippy::too_many_arguments)]
+pub fn diagrams_permutation_test(
+    group_a: Vec<Vec<BirthDeath>>,
+    group_b: Vec<Vec<BirthDeath>>,
+    k: usize,
+    disable_filter: bool,
+    tolerance: Tolerance,
+    p: f64,
+    permutations: usize,
+    seed: u64,
+) -> Result<PermutationTest, Error> {
+    let to_landscapes = |group: Vec<Vec<BirthDeath>>| {
+        group
+            .into_par_iter()
+            .map(|bd_pairs| rpls::pairs_to_landscape(bd_pairs, k, false, disable_filter, tolerance))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let a = to_landscapes(group_a)?;
+    let b = to_landscapes(group_b)?;
+    permutation_test(&a, &b, p, permutations, seed)
+}
@@ -0,0 +1,154 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use crate::birthdeath::BirthDeath;
+use crate::error::Error;
+
+/// Weight of a pair given its birth and persistence
+#[derive(Debug, Clone, Copy)]
+pub enum Weighting {
+    /// Weight equal to the persistence
+    Linear,
+    /// Any function of (birth, persistence)
+    Custom(fn(f64, f64) -> f64),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PersistenceImageConfig {
+    /// Standard deviation of the Gaussian placed on each pair
+    pub sigma: f64,
+    /// Number of pixels along birth and along persistence
+    pub resolution: (usize, usize),
+    /// Birth and persistence ranges covered by the image, taken from the diagram if `None`
+    pub bounds: Option<((f64, f64), (f64, f64))>,
+    pub weighting: Weighting,
+}
+
+impl Default for PersistenceImageConfig {
+    fn default() -> Self {
+        Self {
+            sigma: 0.1,
+            resolution: (20, 20),
+            bounds: None,
+            weighting: Weighting::Linear,
+        }
+    }
+}
+
+// Complementary error function with fractional error below 1.2e-7 (Numerical Recipes erfcc)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / 0.5f64.mul_add(z, 1.0);
+    let poly = [
+        -1.265_512_23, 1.000_023_68, 0.374_091_96, 0.096_784_18, -0.186_288_06,
+        0.278_868_07, -1.135_203_98, 1.488_515_87, -0.822_152_23, 0.170_872_77,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0_f64, |acc, c| acc.mul_add(t, *c));
+    let ans = t * (-z).mul_add(z, poly).exp();
+    if x >= 0.0 { ans } else { 2.0 - ans }
+}
+
+// Probability mass of N(mean, sigma) on [lo, hi]
+fn gaussian_mass(lo: f64, hi: f64, mean: f64, sigma: f64) -> f64 {
+    let scale = sigma * std::f64::consts::SQRT_2;
+    0.5 * (erfc((lo - mean) / scale) - erfc((hi - mean) / scale))
+}
+
+fn range(values: impl Iterator<Item = f64>, sigma: f64) -> (f64, f64) {
+    let (lo, hi) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if !lo.is_finite() {
+        return (0.0, 1.0);
+    }
+    if hi - lo <= f64::EPSILON {
+        return (lo - sigma, hi + sigma);
+    }
+    (lo, hi)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn edges((lo, hi): (f64, f64), n: usize) -> Vec<f64> {
+    (0..=n).map(|i| (hi - lo).mul_add(i as f64 / n as f64, lo)).collect()
+}
+
+fn finite_points(bd_pairs: &[BirthDeath]) -> Vec<(f64, f64)> {
+    bd_pairs
+        .iter()
+        .filter(|bd| bd.death.is_finite() && bd.birth.is_finite())
+        .map(|bd| (bd.birth, bd.death - bd.birth))
+        .collect()
+}
+
+fn bounds_of(points: &[(f64, f64)], config: &PersistenceImageConfig) -> ((f64, f64), (f64, f64)) {
+    config.bounds.unwrap_or_else(|| {
+        (
+            range(points.iter().map(|p| p.0), config.sigma),
+            range(points.iter().map(|p| p.1).chain([0.0]), config.sigma),
+        )
+    })
+}
+
+/// Birth and persistence ranges `generate` covers for `bd_pairs`, either `config.bounds` or the
+/// range of the finite pairs
+#[must_use]
+pub fn bounds(bd_pairs: &[BirthDeath], config: &PersistenceImageConfig) -> ((f64, f64), (f64, f64)) {
+    bounds_of(&finite_points(bd_pairs), config)
+}
+
+/// Birth and persistence ranges covering the finite pairs of every diagram, or `config.bounds`.
+/// Images of several diagrams only line up pixel by pixel when generated with the same bounds.
+#[must_use]
+pub fn common_bounds(diagrams: &[Vec<BirthDeath>], config: &PersistenceImageConfig) -> ((f64, f64), (f64, f64)) {
+    let points: Vec<(f64, f64)> = diagrams.iter().flat_map(|bd_pairs| finite_points(bd_pairs)).collect();
+    bounds_of(&points, config)
+}
+
+/// Persistence image of the finite pairs of `bd_pairs`
+///
+/// Each pair is moved to (birth, death - birth), weighted and spread by a Gaussian which is
+/// integrated over every pixel through the error function. The error function is approximated
+/// with a relative error below 1.2e-7, so pixel masses are accurate to about that much rather
+/// than exact. `image[i][j]` is the pixel at the i-th persistence and j-th birth interval, both
+/// counted from the lower bound, and can be saved with `fileio::write_matrix`.
+///
+/// # Errors
+///
+/// Will return 'Err' if `sigma` is not positive or either resolution is zero
+#[allow(clippy::needless_pass_by_value)]
+pub fn generate(bd_pairs: Vec<BirthDeath>, config: &PersistenceImageConfig) -> Result<Vec<Vec<f64>>, Error> {
+    if config.sigma.is_nan() || config.sigma <= 0.0 {
+        return Err(Error::InvalidArgument("Persistence image sigma must be positive".to_string()));
+    }
+    let (nb, np) = config.resolution;
+    if nb == 0 || np == 0 {
+        return Err(Error::InvalidArgument("Persistence image resolution must be positive".to_string()));
+    }
+    let points = finite_points(&bd_pairs);
+    let (birth_range, persistence_range) = bounds_of(&points, config);
+    let birth_edges = edges(birth_range, nb);
+    let persistence_edges = edges(persistence_range, np);
+
+    let mut image = vec![vec![0.0; nb]; np];
+    for &(birth, persistence) in &points {
+        let w = match config.weighting {
+            Weighting::Linear => persistence,
+            Weighting::Custom(f) => f(birth, persistence),
+        };
+        let birth_mass: Vec<f64> = birth_edges
+            .windows(2)
+            .map(|e| gaussian_mass(e[0], e[1], birth, config.sigma))
+            .collect();
+        for (row, e) in image.iter_mut().zip(persistence_edges.windows(2)) {
+            let mass = w * gaussian_mass(e[0], e[1], persistence, config.sigma);
+            for (pixel, b) in row.iter_mut().zip(&birth_mass) {
+                *pixel = mass.mul_add(*b, *pixel);
+            }
+        }
+    }
+    Ok(image)
+}
@@ -5,37 +5,165 @@
      clippy::cargo,
  )]
 
-use crate::birthdeath::BirthDeath;
+use crate::birthdeath::{self, BirthDeath};
+use crate::error::{Error, EventContext};
+use crate::tolerance::Tolerance;
 use float_ord::FloatOrd;
-use geo::{
-    line_intersection::line_intersection, line_intersection::LineIntersection, Coord, Line
-};
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+
+/// Number type the sweep runs on. Every vertex of a landscape is the midpoint of a birth and a
+/// death, so beyond ordering a type only has to halve.
+pub trait Coordinate: Copy + Ord + fmt::Debug {
+    const ZERO: Self;
+
+    /// `a + (b - a) / 2`
+    fn midpoint(a: Self, b: Self) -> Self;
+
+    /// `(b - a) / 2`
+    fn half_difference(a: Self, b: Self) -> Self;
+
+    /// Whether two values are the same under `tolerance`
+    fn approx_eq(a: Self, b: Self, tolerance: Tolerance) -> bool;
+
+    /// Whether two computed values can differ only by the rounding of the computation
+    fn within_rounding(a: Self, b: Self) -> bool;
+
+    /// `a.0 + a.1` compared to `b.0 + b.1` without rounding the sums
+    fn cmp_sum(a: (Self, Self), b: (Self, Self)) -> std::cmp::Ordering;
+
+    fn to_f64(self) -> f64;
+}
+
+// a + b = s + e exactly (Knuth's two-sum)
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let virtual_b = s - a;
+    let virtual_a = s - virtual_b;
+    (s, (a - virtual_a) + (b - virtual_b))
+}
+
+impl Coordinate for FloatOrd<f64> {
+    const ZERO: Self = Self(0.0);
+
+    fn midpoint(a: Self, b: Self) -> Self {
+        Self((b.0 - a.0) / 2.0 + a.0)
+    }
+
+    fn half_difference(a: Self, b: Self) -> Self {
+        Self((b.0 - a.0) / 2.0)
+    }
+
+    fn approx_eq(a: Self, b: Self, tolerance: Tolerance) -> bool {
+        tolerance.approx_eq(a.0, b.0)
+    }
+
+    // A midpoint is rounded once in the subtraction, once in the halving and once in the sum
+    fn within_rounding(a: Self, b: Self) -> bool {
+        Tolerance::Ulps(4).approx_eq(a.0, b.0)
+    }
+
+    // The error of a rounded sum is at most half an ulp of it, so sums that round apart are in
+    // the same order as their roundings and only the errors of equal ones need comparing
+    fn cmp_sum(a: (Self, Self), b: (Self, Self)) -> std::cmp::Ordering {
+        let (a_sum, a_error) = two_sum(a.0.0, a.1.0);
+        let (b_sum, b_error) = two_sum(b.0.0, b.1.0);
+        Self(a_sum).cmp(&Self(b_sum)).then_with(|| Self(a_error).cmp(&Self(b_error)))
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0
+    }
+}
+
+/// Exact multiple of one half, the value is `halves() / 2`
+///
+/// The tents of integer pairs only ever meet at half integers so a landscape of integer pairs is
+/// exact in this type. Values only come out of `generate_exact`, the sweep relies on births and
+/// deaths being whole numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HalfUnits(i128);
+
+impl HalfUnits {
+    pub(crate) const fn from_integer(value: i64) -> Self {
+        Self(2 * value as i128)
+    }
+
+    /// Number of halves, twice the value
+    #[must_use]
+    pub const fn halves(self) -> i128 {
+        self.0
+    }
+}
+
+impl Coordinate for HalfUnits {
+    const ZERO: Self = Self(0);
+
+    // Only ever called on a birth and a death, which are whole numbers of units, so the sum of
+    // halves is even and the division exact
+    fn midpoint(a: Self, b: Self) -> Self {
+        Self((a.0 + b.0) / 2)
+    }
+
+    fn half_difference(a: Self, b: Self) -> Self {
+        Self((b.0 - a.0) / 2)
+    }
+
+    // Nothing is rounded so only equal values are the same
+    fn approx_eq(a: Self, b: Self, _tolerance: Tolerance) -> bool {
+        a == b
+    }
+
+    fn within_rounding(a: Self, b: Self) -> bool {
+        a == b
+    }
+
+    fn cmp_sum(a: (Self, Self), b: (Self, Self)) -> std::cmp::Ordering {
+        (a.0.0 + a.1.0).cmp(&(b.0.0 + b.1.0))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / 2.0
+    }
+}
+
+impl fmt::Display for HalfUnits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let halves = self.0.unsigned_abs();
+        if halves % 2 == 0 {
+            write!(f, "{sign}{}", halves / 2)
+        } else {
+            write!(f, "{sign}{}.5", halves / 2)
+        }
+    }
+}
 
 #[derive(Debug)]
-struct PersistenceMountain {
+pub(crate) struct PersistenceMountain<C: Coordinate = FloatOrd<f64>> {
     position: Option<usize>,
     slope_rising: bool,
-    birth: PointOrd,
-    middle: PointOrd,
-    death: PointOrd,
+    pub(crate) birth: PointOrd<C>,
+    pub(crate) middle: PointOrd<C>,
+    pub(crate) death: PointOrd<C>,
     id: usize,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct PointOrd {
-    pub x: FloatOrd<f64>,
-    pub y: FloatOrd<f64>,
+pub struct PointOrd<C = FloatOrd<f64>> {
+    pub x: C,
+    pub y: C,
 }
 
-impl Ord for PointOrd {
+impl<C: Ord> Ord for PointOrd<C> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
          self.x.cmp(&other.x)
     }
 }
 
-impl PartialOrd for PointOrd {
+impl<C: Ord> PartialOrd for PointOrd<C> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
@@ -56,77 +184,125 @@ enum EventType {
 }
 
 #[derive(Debug)]
-struct Event {
-    value: PointOrd,
+#[allow(clippy::struct_field_names)]
+struct Event<C> {
+    value: PointOrd<C>,
     event_type: EventType,
     parent_mountain_id: usize,
     parent_mountain2_id: Option<usize>,
+    /// Birth and death of the parent mountain, used to break ties
+    tent: (C, C),
+}
+
+impl<C: Coordinate> Event<C> {
+    // Order of events of the same type at the same x. Of two mountains born together the shorter
+    // enters first and the two swap at its peak, of two peaking together the taller turns first,
+    // and of two dying together the shorter, which is below, dies first. Peaks can round to the
+    // same x, so they are first ordered by the unrounded sum of birth and death. Only identical
+    // mountains are left tied and they produce the same points in either order.
+    fn tie_break(&self, other: &Self) -> std::cmp::Ordering {
+        let ((birth, death), (other_birth, other_death)) = (&self.tent, &other.tent);
+        match self.event_type {
+            EventType::Up => death.cmp(other_death),
+            EventType::Down => C::cmp_sum(self.tent, other.tent).then_with(|| birth.cmp(other_birth)),
+            EventType::Death => other_birth.cmp(birth),
+            EventType::Intersection => std::cmp::Ordering::Equal,
+        }
+    }
 }
 
 // NOTE: This is opposite on purpose to flip to built in BinaryHeap
-impl Ord for Event {
-    // Compare points then event_type
+impl<C: Coordinate> Ord for Event<C> {
+    // Total order: x, then event_type, then the tie break above, then the mountain ids so that
+    // the heap never has to choose between equal events
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.value != other.value{
-            return self.value.cmp(&other.value).reverse()
-        }
-        // if self.event_type != other.event_type{
-        self.event_type.cmp(&other.event_type).reverse()
-        // }
-        // if self.parent_mountain_id != other.parent_mountain_id {
-        //     return self.parent_mountain_id.cmp(&other.parent_mountain_id).reverse();
-        // }
-        // self.parent_mountain2_id.cmp(&other.parent_mountain2_id).reverse()
+        self.value
+            .cmp(&other.value)
+            .then_with(|| self.event_type.cmp(&other.event_type))
+            .then_with(|| self.tie_break(other))
+            .then_with(|| self.parent_mountain_id.cmp(&other.parent_mountain_id))
+            .then_with(|| self.parent_mountain2_id.cmp(&other.parent_mountain2_id))
+            .reverse()
     }
 }
 
-impl PartialOrd for Event {
+impl<C: Coordinate> PartialOrd for Event<C> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl PartialEq for Event {
+impl<C: Coordinate> PartialEq for Event<C> {
     fn eq(&self, other: &Self) -> bool {
-        self.parent_mountain_id == other.parent_mountain_id
-            && self.parent_mountain2_id == other.parent_mountain2_id
+        self.cmp(other) == std::cmp::Ordering::Equal
     }
 }
 
-impl Eq for Event {}
+impl<C: Coordinate> Eq for Event<C> {}
+
+fn context<C: Coordinate>(event: &Event<C>) -> EventContext {
+    EventContext {
+        kind: match event.event_type {
+            EventType::Death => "Death",
+            EventType::Up => "Up",
+            EventType::Down => "Down",
+            EventType::Intersection => "Intersection",
+        },
+        x: event.value.x.to_f64(),
+        y: event.value.y.to_f64(),
+        mountain: event.parent_mountain_id,
+        neighbor: event.parent_mountain2_id,
+    }
+}
 
-fn create_mountain(birth: f64, death: f64, index: usize) -> PersistenceMountain {
-    let half_dist = (death - birth) / 2.0;
+fn invariant<C: Coordinate>(message: impl Into<String>, event: &Event<C>) -> Error {
+    Error::Invariant {
+        message: message.into(),
+        event: Some(context(event)),
+    }
+}
 
+fn create_mountain<C: Coordinate>(birth: C, death: C, index: usize) -> PersistenceMountain<C> {
     PersistenceMountain {
         position: None,
         slope_rising: true,
         birth: PointOrd {
-            x: FloatOrd(birth),
-            y: FloatOrd(0.0),
+            x: birth,
+            y: C::ZERO,
         },
         middle: PointOrd {
-            x: FloatOrd(half_dist + birth),
-            y: FloatOrd(half_dist),
+            x: C::midpoint(birth, death),
+            y: C::half_difference(birth, death),
         },
         death: PointOrd {
-            x: FloatOrd(death),
-            y: FloatOrd(0.0),
+            x: death,
+            y: C::ZERO,
         },
         id: index,
     }
 }
 
-fn generate_mountains(bd_pairs: Vec<BirthDeath>) -> Vec<PersistenceMountain> {
+fn finite_pairs(bd_pairs: Vec<BirthDeath>) -> Vec<(FloatOrd<f64>, FloatOrd<f64>)> {
     bd_pairs
         .into_iter()
         .filter(|BirthDeath { birth, death }| death.is_finite() && birth.is_finite())
+        .map(|BirthDeath { birth, death }| (FloatOrd(birth), FloatOrd(death)))
+        .collect()
+}
+
+fn mountains_of<C: Coordinate>(bd_pairs: &[(C, C)]) -> Vec<PersistenceMountain<C>> {
+    bd_pairs
+        .iter()
         .enumerate()
-        .map(|(i, BirthDeath { birth, death })| create_mountain(birth, death, i))
-        .collect::<Vec<PersistenceMountain>>()
+        .map(|(i, &(birth, death))| create_mountain(birth, death, i))
+        .collect()
 }
 
-fn generate_initial_events(mountains: &Vec<&mut PersistenceMountain>) -> Vec<Event> {
+pub(crate) fn generate_mountains(bd_pairs: Vec<BirthDeath>) -> Vec<PersistenceMountain> {
+    mountains_of(&finite_pairs(bd_pairs))
+}
+
+fn generate_initial_events<C: Coordinate>(mountains: &Vec<&mut PersistenceMountain<C>>) -> Vec<Event<C>> {
     mountains
         .iter()
         .flat_map(
@@ -143,18 +319,21 @@ fn generate_initial_events(mountains: &Vec<&mut PersistenceMountain>) -> Vec<Eve
                         event_type: EventType::Up,
                         parent_mountain_id: *id,
                         parent_mountain2_id: None,
+                        tent: (birth.x, death.x),
                     },
                     Event {
                         value: middle.clone(),
                         event_type: EventType::Down,
                         parent_mountain_id: *id,
                         parent_mountain2_id: None,
+                        tent: (birth.x, death.x),
                     },
                     Event {
                         value: death.clone(),
                         event_type: EventType::Death,
                         parent_mountain_id: *id,
                         parent_mountain2_id: None,
+                        tent: (birth.x, death.x),
                     },
                 ]
             },
@@ -162,69 +341,44 @@ fn generate_initial_events(mountains: &Vec<&mut PersistenceMountain>) -> Vec<Eve
         .collect()
 }
 
-const fn current_segment_start(mountain: &PersistenceMountain) -> (f64, f64) {
-    if mountain.slope_rising {
-            ( mountain.birth.x.0, mountain.birth.y.0 )
-    } else { 
-        ( mountain.middle.x.0, mountain.middle.y.0 )
-    }
-}
-
-const fn current_segment_end(mountain: &PersistenceMountain) -> (f64,f64) {
-    if mountain.slope_rising {
-        ( mountain.middle.x.0, mountain.middle.y.0 )
-    } else {
-        ( mountain.death.x.0, mountain.death.y.0)
-    }
-}
-
-fn create_line_segment(mountain: &PersistenceMountain) -> Line<f64> {
-    Line {
-        start: current_segment_start(mountain).into(),
-        end: current_segment_end(mountain).into(),
-    }
-}
-
-fn intersects_with_neighbor(m1: &PersistenceMountain, m2: &PersistenceMountain) -> Option<PointOrd> {
+// Every rising segment lies on y = x - birth and every falling one on y = death - x, so the lines
+// of a rising and a falling mountain cross at ((birth + death) / 2, (death - birth) / 2). That
+// point is on the rising segment [birth, middle] iff birth <= death <= its own death and on the
+// falling segment [middle, death] iff its own birth <= birth <= death. The test only compares
+// inputs so it is exact for any coordinate. Segments of the same slope either miss or overlap
+// along a shared birth or death, where both mountains have the same height and neither order is
+// wrong. The overlap ends or starts at the peak of one of them, which is an endpoint that the
+// test above counts, so the two swap exactly there.
+fn intersects_with_neighbor<C: Coordinate>(m1: &PersistenceMountain<C>, m2: &PersistenceMountain<C>) -> Option<PointOrd<C>> {
     if m1.slope_rising == m2.slope_rising {
         return None;
     }
-    let inter = line_intersection(create_line_segment(m1), create_line_segment(m2));
-    match inter {
-        Some(LineIntersection::SinglePoint {
-            intersection: Coord { x, y },
-            ..
-        }) => Some(PointOrd {
-            x: min(FloatOrd(x), min(m1.death.x, m2.death.x)),
-            y: FloatOrd(y),
-        }),
-        // Ignore all colinnear, not proper and no intersection results these will be resolved on
-        // slope change or do not matter
-        Some(i) => match i {
-            LineIntersection::SinglePoint { intersection: _, is_proper: _ } |
-                LineIntersection::Collinear { intersection: _ } => None
-
-        },
-        _ => None
+    let (rising, falling) = if m1.slope_rising { (m1, m2) } else { (m2, m1) };
+    let (birth, death) = (rising.birth.x, falling.death.x);
+    if falling.birth.x > birth || birth > death || death > rising.death.x {
+        return None;
     }
+    // The rounded crossing can land on, or a step behind, a vertex another pair already put on
+    // the level, `log_checks` merges those
+    Some(PointOrd {
+        // Rounding must not move the point past either death
+        x: min(C::midpoint(birth, death), min(m1.death.x, m2.death.x)),
+        y: C::half_difference(birth, death),
+    })
 }
 
-fn float_equal(a:f64, b:f64) -> bool{
-    (a - b).abs() < f64::EPSILON
+fn same_point<C: Coordinate>(p1: (C, C), p2: (C, C), tolerance: Tolerance) -> bool {
+    C::approx_eq(p1.0, p2.0, tolerance) && C::approx_eq(p1.1, p2.1, tolerance)
 }
 
-fn float_point_check(p1: (f64,f64), p2: (f64,f64))-> bool{
-    float_equal(p1.0, p2.0) && 
-        float_equal(p1.1, p2.1)
-}
-
-fn log_checks(
-    _mountain: &PersistenceMountain,
-    event: &Event,
-    landscapes: &[Vec<(f64,f64)>],
+fn log_checks<C: Coordinate>(
+    _mountain: &PersistenceMountain<C>,
+    event: &Event<C>,
+    landscapes: &mut [Vec<(C, C)>],
     _k: usize,
-    position: usize
-    )-> bool{
+    position: usize,
+    tolerance: Tolerance,
+    )-> Result<bool, Error>{
     // return true;
         // Don't log points twice This is fine to prevent ordering problems if start and end points
         // are the same, avoids perfect ordering
@@ -232,81 +386,98 @@ fn log_checks(
         //     assert_ne!(*landscapes[*position].last().unwrap(), event.value);
         // }
         // Ensure points are increasing x (except if points are exactly the same)
-        if ! landscapes[position].is_empty(){
-            if float_point_check(*landscapes[position].last().unwrap(), (event.value.x.0, event.value.y.0)) {
+        if let Some(last) = landscapes[position].last_mut(){
+            if same_point(*last, (event.value.x, event.value.y), tolerance) {
                 // Ignore, this is fine. They are the same
-                return false;
+                return Ok(false);
+            }
+            // Vertices computed from different pairs can round to the same x, or even put the later
+            // one a step behind, while their heights keep digits that x lost. They are one vertex.
+            // A birth or death is exact and has every other height at this x within rounding of
+            // zero, otherwise the higher height is the one on this level.
+            if last.0 >= event.value.x && C::within_rounding(last.0, event.value.x) {
+                if last.1 != C::ZERO {
+                    last.1 = if event.value.y == C::ZERO { C::ZERO } else { max(last.1, event.value.y) };
+                }
+                return Ok(false);
             }
             // else{
-                assert!(landscapes[position].last().unwrap().0 < event.value.x.0,
-                "Last x in landscape {} is ({},{}) but new point to be added has an x of ({},{})", 
-                position,
-                landscapes[position].last().unwrap().0, 
-                landscapes[position].last().unwrap().1, 
-                event.value.x.0,
-                event.value.y.0
-                ); 
+                if last.0 >= event.value.x {
+                    return Err(invariant(format!(
+                        "Last x in landscape {} is ({},{}) but new point to be added has an x of ({},{})",
+                        position,
+                        last.0.to_f64(),
+                        last.1.to_f64(),
+                        event.value.x.to_f64(),
+                        event.value.y.to_f64()
+                    ), event));
+                }
             // }
         }
         // Ensure birth/death is in bottom most landscape (exception if the nearest is a tie, they
         // are just dieing out of order and the other must die right after)
         let below = position + 1;
-        if float_equal(event.value.y.0, 0.0) &&
-            below < landscapes.len() && 
-            ! landscapes[below].is_empty(){
-                if float_point_check(*landscapes[below].last().unwrap(), *landscapes[position].last().unwrap()){
+        if C::approx_eq(event.value.y, C::ZERO, tolerance) && below < landscapes.len(){
+            if let Some(&below_last) = landscapes[below].last(){
+                if landscapes[position].last().is_some_and(|&last| same_point(below_last, last, tolerance)){
                     // This is fine, ignore. See above comment
                 }
-                else{
+                else if !C::approx_eq(below_last.1, C::ZERO, tolerance){
                     // println!("{:?}", landscapes[below].last().unwrap());
                     // println!("{:?}", landscapes[position].last().unwrap());
                     // println!("{:?}", mountain);
-                    assert!(float_equal(landscapes[below].last().unwrap().1, 0.0),
-                        "Attempting to add a birth/death ({},{}) to higher landscape {} when {} is non zero ({},{})", 
-                        event.value.x.0,
-                        event.value.y.0,
+                    return Err(invariant(format!(
+                        "Attempting to add a birth/death ({},{}) to higher landscape {} when {} is non zero ({},{})",
+                        event.value.x.to_f64(),
+                        event.value.y.to_f64(),
                         position,
                         below,
-                        landscapes[below].last().unwrap().0,
-                        landscapes[below].last().unwrap().1,
-                    ); 
+                        below_last.0.to_f64(),
+                        below_last.1.to_f64(),
+                    ), event));
                 }
+            }
         }
-        true
+        Ok(true)
 }
 
-fn log_to_landscape(
-    mountain: &PersistenceMountain,
-    event: &Event,
-    landscapes: &mut [Vec<(f64,f64)>],
+fn log_to_landscape<C: Coordinate>(
+    mountain: &PersistenceMountain<C>,
+    event: &Event<C>,
+    landscapes: &mut [Vec<(C, C)>],
     k: usize,
-    mountain2: Option<&PersistenceMountain>
-) {
-    let position = mountain.position.expect("Mountain with event is dead");
+    mountain2: Option<&PersistenceMountain<C>>,
+    tolerance: Tolerance,
+) -> Result<(), Error> {
+    let position = mountain.position.ok_or_else(|| invariant("Mountain with event is dead", event))?;
     if position < k &&
-        log_checks(mountain, event, landscapes, k, position){
-            landscapes[position].push((event.value.x.0, event.value.y.0));
+        log_checks(mountain, event, landscapes, k, position, tolerance)?{
+            landscapes[position].push((event.value.x, event.value.y));
     }
 
     if let Some(m2) = mountain2{
-        let position = m2.position.expect("Mountain with event is dead");
+        let position = m2.position.ok_or_else(|| invariant("Mountain with event is dead", event))?;
         if position < k &&
-            log_checks(m2, event, landscapes, k, position){
-                landscapes[position].push((event.value.x.0, event.value.y.0));
+            log_checks(m2, event, landscapes, k, position, tolerance)?{
+                landscapes[position].push((event.value.x, event.value.y));
         }
     }
+    Ok(())
 }
 
-fn find_intersection(
+fn find_intersection<C: Coordinate>(
     status: &VecDeque<usize>,
     parent_mountain_id: usize,
-    mountains: &[&mut PersistenceMountain],
+    mountains: &[&mut PersistenceMountain<C>],
     direction_to_check: &Direction,
-) -> Option<Event> {
-    let position = mountains[parent_mountain_id].position.expect("Intersection check for dead mountain");
+) -> Result<Option<Event<C>>, Error> {
+    let position = mountains[parent_mountain_id].position.ok_or_else(|| Error::Invariant {
+        message: format!("Intersection check for dead mountain {parent_mountain_id}"),
+        event: None,
+    })?;
     // Stop underflow of unsigned number
     if position == 0 && *direction_to_check == Direction::Above {
-        return None;
+        return Ok(None);
     }
     let neighbor_index = match direction_to_check {
         Direction::Below => position + 1,
@@ -315,17 +486,18 @@ fn find_intersection(
 
     if let Some(neighbor) = status.get(neighbor_index) {
         if let Some(intersection) = intersects_with_neighbor(mountains[parent_mountain_id], mountains[*neighbor]) {
-            return Some(Event {
+            return Ok(Some(Event {
                 value: intersection,
                 event_type: EventType::Intersection,
                 parent_mountain_id,
                 parent_mountain2_id: Some(*neighbor),
-            })
+                tent: (mountains[parent_mountain_id].birth.x, mountains[parent_mountain_id].death.x),
+            }))
             // println!("{intersection:?}");
             // return Some(intersection);
         }
     }
-    None
+    Ok(None)
 }
 
 #[must_use]
@@ -338,11 +510,9 @@ pub fn empty_landscape(k: usize) -> Vec<Vec<(f64,f64)>>{
     landscapes
 }
 
-fn handle_up(state: &mut State, event: &Event){
+fn handle_up<C: Coordinate>(state: &mut State<C>, event: &Event<C>) -> Result<(), Error>{
     // Add to status structure
-    let start_len = state.status.len();
     state.status.push_back(event.parent_mountain_id);
-    assert!(start_len + 1 == state.status.len());
     let position = state.status.len() - 1;
     state.mountains[event.parent_mountain_id].position = Some(position);
 
@@ -353,27 +523,28 @@ fn handle_up(state: &mut State, event: &Event){
         event,
         &mut state.landscapes,
         state.k,
-        None
-        );
+        None,
+        state.tolerance,
+        )?;
     // Check and handle all intersections
     let new_event = find_intersection(
         &state.status,
         parent_mountain_id,
         state.mountains,
         &Direction::Above,
-        );
+        )?;
     if let Some(intersection) = new_event{
-        handle_intersection(state, intersection);
+        handle_intersection(state, intersection)?;
     }
+    Ok(())
 }
 
-fn handle_intersection(state: &mut State, event: Event){
+fn handle_intersection<C: Coordinate>(state: &mut State<C>, event: Event<C>) -> Result<(), Error>{
     state.weird_q.push_back(event);
-    while ! state.weird_q.is_empty(){
-        let event = state.weird_q.pop_front().unwrap();
-        let parent_mountain2_id = event
+    while let Some(event) = state.weird_q.pop_front(){
+        let neighbor_id = event
             .parent_mountain2_id
-            .expect("Intersection event with no second mountain");
+            .ok_or_else(|| invariant("Intersection event with no second mountain", &event))?;
         let parent_mountain_id = event.parent_mountain_id;
 
         // Add to ouput if needed
@@ -382,8 +553,9 @@ fn handle_intersection(state: &mut State, event: Event){
             &event,
             &mut state.landscapes,
             state.k,
-            Some(state.mountains[parent_mountain2_id])
-        );
+            Some(state.mountains[neighbor_id]),
+            state.tolerance,
+        )?;
         // log_to_landscape(
         //     state.mountains[parent_mountain2_id], 
         //     event, 
@@ -393,20 +565,23 @@ fn handle_intersection(state: &mut State, event: Event){
         let lower_id = if state.mountains[parent_mountain_id].slope_rising {
             parent_mountain_id
         } else{
-            parent_mountain2_id
+            neighbor_id
         // )
         };
         let upper_id = if state.mountains[parent_mountain_id].slope_rising {
-            parent_mountain2_id
+            neighbor_id
         } else{
             parent_mountain_id
         };
         // Swap
-        state.status.swap(
-            state.mountains[upper_id].position.expect("Dead mountain in intersection event"),
-            state.mountains[lower_id].position.expect("Dead mountain in intersection event"),
-        );
-        assert!(state.mountains[upper_id].position != state.mountains[lower_id].position);
+        let (Some(upper_position), Some(lower_position)) =
+            (state.mountains[upper_id].position, state.mountains[lower_id].position) else {
+            return Err(invariant("Dead mountain in intersection event", &event));
+        };
+        if upper_position == lower_position {
+            return Err(invariant("Intersecting mountains share a position", &event));
+        }
+        state.status.swap(upper_position, lower_position);
         let tmp = state.mountains[lower_id].position;
         state.mountains[lower_id].position = state.mountains[upper_id].position;
         state.mountains[upper_id].position = tmp;
@@ -414,33 +589,40 @@ fn handle_intersection(state: &mut State, event: Event){
         // Must check both ways because of no sorting, intersections can be discovered in both
         // directions
         if let Some(new_event) =
-            find_intersection(&state.status, lower_id, state.mountains, &Direction::Above)
+            find_intersection(&state.status, lower_id, state.mountains, &Direction::Above)?
         {
             // handle_intersection(state, &new_event);
             state.weird_q.push_back(new_event);
         }
         if let Some(new_event) =
-            find_intersection(&state.status, upper_id, state.mountains, &Direction::Below)
+            find_intersection(&state.status, upper_id, state.mountains, &Direction::Below)?
         {
             // handle_intersection(state, new_event);
             state.weird_q.push_back(new_event);
         }
     }
+    Ok(())
 }
 
 
-fn handle_death(state: &mut State, event: &Event){
-    let _pos = state.mountains[event.parent_mountain_id]
+fn handle_death<C: Coordinate>(state: &mut State<C>, event: &Event<C>) -> Result<(), Error>{
+    let pos = state.mountains[event.parent_mountain_id]
         .position
-        .expect("Death of dead mountain");
-    // Check for floating point mess up on death/intersection Ordering
-    // TODO: What is this???? feels like a bug and a bad hotfix
-    // let weird_q = &mut VecDeque::new();
-    // if pos != state.status.len() - 1 {
-    //     while pos < state.status.len() - 1 {
-    //         weird_q.push_back(state.status.pop_back().unwrap());
-    //     }
-    // }
+        .ok_or_else(|| invariant("Death of dead mountain", event))?;
+    // The dying mountain is at height zero so every mountain below it is too, and those can only be
+    // dying at the same point. When deaths tie the dying mountain need not be last, swapping it
+    // with the last one exchanges two mountains that meet at this point and keeps the levels
+    // below it intact.
+    let last = state.status.len() - 1;
+    if pos != last {
+        let last_id = state.status[last];
+        if state.mountains[last_id].death.x != event.value.x {
+            return Err(invariant("Mountain below a dying mountain is still alive", event));
+        }
+        state.status.swap(pos, last);
+        state.mountains[last_id].position = Some(pos);
+        state.mountains[event.parent_mountain_id].position = Some(last);
+    }
     let parent_mountain_id = event.parent_mountain_id;
 
     // Add to ouput if needed
@@ -449,26 +631,16 @@ fn handle_death(state: &mut State, event: &Event){
         event,
         &mut state.landscapes,
         state.k,
-        None
-        );
+        None,
+        state.tolerance,
+        )?;
     // remove and disable
     state.status.pop_back();
     state.mountains[parent_mountain_id].position = None;
-    // TODO: Same here???? L -17
-    // while !weird_q.is_empty() {
-    //     let element = weird_q.pop_back().unwrap();
-    //     state.mountains[element].position = Some(state.mountains[element].position.unwrap() - 1);
-    //     log_to_landscape(
-    //         state.mountains[element],
-    //         event.value,
-    //         &mut state.landscapes,
-    //         state.k,
-    //         );
-    //     state.status.push_back(element);
-    // }
-}
-
-fn handle_down(state: &mut State, event: &Event){
+    Ok(())
+}
+
+fn handle_down<C: Coordinate>(state: &mut State<C>, event: &Event<C>) -> Result<(), Error>{
     // Update status structures
     state.mountains[event.parent_mountain_id].slope_rising = false;
     let parent_mountain_id = event.parent_mountain_id;
@@ -479,49 +651,105 @@ fn handle_down(state: &mut State, event: &Event){
         event,
         &mut state.landscapes,
         state.k,
-        None
-        );
+        None,
+        state.tolerance,
+        )?;
     // Check for intersections
     let new_event = find_intersection(
         &state.status,
         parent_mountain_id,
         state.mountains,
         &Direction::Below,
-        );
+        )?;
     // println!("{new_event:?}");
     if let Some(intersection) = new_event{
-        handle_intersection(state, intersection);
+        handle_intersection(state, intersection)?;
     }
-    
+    Ok(())
 }
 
 
 #[derive(Debug)]
-struct State<'a>{
+struct State<'a, C: Coordinate>{
     status: VecDeque<usize>,
-    mountains: &'a mut Vec<&'a mut PersistenceMountain>,
-    landscapes: Vec<Vec<(f64,f64)>>,
-    events: BinaryHeap<Event>,
+    mountains: &'a mut Vec<&'a mut PersistenceMountain<C>>,
+    landscapes: Vec<Vec<(C, C)>>,
+    events: BinaryHeap<Event<C>>,
     k: usize,
-    weird_q: VecDeque<Event>
+    weird_q: VecDeque<Event<C>>,
+    tolerance: Tolerance,
 }
 
-/// # Panics
+/// `generate_with_tolerance` with the default tolerance
 ///
-/// Will panic if invalid state is discovered during generation
-#[must_use]
-pub fn generate(bd_pairs: Vec<BirthDeath>, k: usize, debug: bool) -> Vec<Vec<(f64,f64)>> {
-    let mut binding = generate_mountains(bd_pairs);
-    let mut mountains: Vec<&mut PersistenceMountain> 
+/// # Errors
+///
+/// Will return 'Err' if a pair is invalid or invalid state is discovered during generation
+pub fn generate(bd_pairs: Vec<BirthDeath>, k: usize, debug: bool) -> Result<Vec<Vec<(f64,f64)>>, Error> {
+    generate_with_tolerance(bd_pairs, k, debug, Tolerance::default())
+}
+
+/// Landscape of `bd_pairs` where vertices that are the same under `tolerance` are merged
+///
+/// Vertices whose x only differs by the rounding of the sweep are merged under any tolerance,
+/// including `Tolerance::EXACT`, keeping the higher height unless one of them is zero.
+///
+/// # Errors
+///
+/// Will return 'Err' if a pair is invalid or invalid state is discovered during generation
+pub fn generate_with_tolerance(bd_pairs: Vec<BirthDeath>, k: usize, debug: bool, tolerance: Tolerance) -> Result<Vec<Vec<(f64,f64)>>, Error> {
+    birthdeath::check_pairs(&bd_pairs)?;
+    let landscapes = generate_with(&finite_pairs(bd_pairs), k, debug, tolerance)?;
+    Ok(landscapes
+        .into_iter()
+        .map(|level| level.into_iter().map(|(x, y)| (x.0, y.0)).collect())
+        .collect())
+}
+
+/// Landscape of integer pairs with every vertex computed exactly, no rounding and no tolerance is
+/// involved. Dyadic filtrations can be scaled to integers first.
+///
+/// # Errors
+///
+/// Will return 'Err' if a pair dies before it is born or invalid state is discovered during
+/// generation
+pub fn generate_exact(bd_pairs: &[(i64, i64)], k: usize, debug: bool) -> Result<Vec<Vec<(HalfUnits, HalfUnits)>>, Error> {
+    let pairs: Vec<(HalfUnits, HalfUnits)> = bd_pairs
+        .iter()
+        .map(|&(birth, death)| (HalfUnits::from_integer(birth), HalfUnits::from_integer(death)))
+        .collect();
+    generate_with(&pairs, k, debug, Tolerance::EXACT)
+}
+
+// Landscape of pairs in any `Coordinate`, `generate` and `generate_exact` are this sweep on
+// `FloatOrd<f64>` and `HalfUnits`
+pub(crate) fn generate_with<C: Coordinate>(bd_pairs: &[(C, C)], k: usize, debug: bool, tolerance: Tolerance) -> Result<Vec<Vec<(C, C)>>, Error> {
+    if let Some((index, &(birth, death))) = bd_pairs.iter().enumerate().find(|(_, (birth, death))| death < birth) {
+        return Err(Error::InvalidPair {
+            index,
+            birth: birth.to_f64(),
+            death: death.to_f64(),
+        });
+    }
+    // A pair that dies when it is born has a tent of zero height, it never shows in any level. So
+    // does one whose peak rounds onto its birth or death, which is at most a unit of rounding tall
+    let bd_pairs: Vec<(C, C)> = bd_pairs
+        .iter()
+        .copied()
+        .filter(|&(birth, death)| birth < C::midpoint(birth, death) && C::midpoint(birth, death) < death)
+        .collect();
+    let mut binding = mountains_of(&bd_pairs);
+    let mut mountains: Vec<&mut PersistenceMountain<C>> 
         = binding.iter_mut().collect();
 
     let mut state = State{
         events: BinaryHeap::from(generate_initial_events(&mountains)),
         status: VecDeque::new(),
         mountains: &mut mountains,
-        landscapes: empty_landscape(k),
+        landscapes: (0..k).map(|_| Vec::new()).collect(),
         k,
         weird_q: VecDeque::new(),
+        tolerance,
     };
 
     while let Some(event) = state.events.pop(){
@@ -530,17 +758,17 @@ pub fn generate(bd_pairs: Vec<BirthDeath>, k: usize, debug: bool) -> Vec<Vec<(f6
         }
         match event.event_type {
             EventType::Up => {
-                handle_up(&mut state, &event);
+                handle_up(&mut state, &event)?;
             }
             EventType::Down => {
-                handle_down(&mut state, &event);
+                handle_down(&mut state, &event)?;
             }
             EventType::Death => {
-                handle_death(&mut state, &event);
+                handle_death(&mut state, &event)?;
             }
-            EventType::Intersection => unreachable!("Event type should not be here")
+            EventType::Intersection => return Err(invariant("Event type should not be here", &event))
         }
     }
 
-    state.landscapes
+    Ok(state.landscapes)
 }
@@ -0,0 +1,178 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use crate::error::Error;
+
+// A landscape level is a piecewise linear function given by its vertices in increasing x. Outside
+// of the first and last vertex the function is zero.
+
+/// Value of a single landscape level at `x`
+#[must_use]
+pub fn evaluate(level: &[(f64,f64)], x: f64) -> f64 {
+    let (Some(first), Some(last)) = (level.first(), level.last()) else {
+        return 0.0;
+    };
+    if x < first.0 || x > last.0 {
+        return 0.0;
+    }
+    let i = level.partition_point(|p| p.0 < x);
+    if i == 0 {
+        return level[0].1;
+    }
+    if i == level.len() {
+        return last.1;
+    }
+    let (a, b) = (level[i - 1], level[i]);
+    if b.0 <= a.0 || b.0 <= x {
+        return b.1;
+    }
+    a.1 + (b.1 - a.1) * (x - a.0) / (b.0 - a.0)
+}
+
+/// Sorted union of every vertex x of `levels` with duplicates removed
+#[must_use]
+pub fn breakpoints(levels: &[&[(f64,f64)]]) -> Vec<f64> {
+    let mut xs: Vec<f64> = levels
+        .iter()
+        .flat_map(|level| level.iter().map(|p| p.0))
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    xs
+}
+
+/// Exact pointwise sum of two landscape levels
+#[must_use]
+pub fn add(a: &[(f64,f64)], b: &[(f64,f64)]) -> Vec<(f64,f64)> {
+    if a.is_empty() {
+        return b.to_vec();
+    }
+    if b.is_empty() {
+        return a.to_vec();
+    }
+    breakpoints(&[a, b])
+        .into_iter()
+        .map(|x| (x, evaluate(a, x) + evaluate(b, x)))
+        .collect()
+}
+
+/// Landscape level multiplied pointwise by `c`
+#[must_use]
+pub fn scale(level: &[(f64,f64)], c: f64) -> Vec<(f64,f64)> {
+    level.iter().map(|&(x, y)| (x, y * c)).collect()
+}
+
+// Level `i` of a landscape, missing levels are the zero function
+pub(crate) fn level(landscape: &[Vec<(f64,f64)>], i: usize) -> &[(f64,f64)] {
+    landscape.get(i).map_or(&[], Vec::as_slice)
+}
+
+// Integral of |d|^p over a width `h` where d changes linearly from `d0` to `d1`
+fn segment_abs_pow(d0: f64, d1: f64, h: f64, p: f64) -> f64 {
+    if h <= 0.0 {
+        return 0.0;
+    }
+    if (d0 < 0.0 && d1 > 0.0) || (d0 > 0.0 && d1 < 0.0) {
+        let t = d0 / (d0 - d1);
+        return segment_abs_pow(d0, 0.0, h * t, p) + segment_abs_pow(0.0, d1, h * (1.0 - t), p);
+    }
+    let (a, b) = (d0.abs(), d1.abs());
+    if (p - 1.0).abs() < f64::EPSILON {
+        return h * (a + b) / 2.0;
+    }
+    if (p - 2.0).abs() < f64::EPSILON {
+        return h * a.mul_add(a, b.mul_add(b, a * b)) / 3.0;
+    }
+    if (b - a).abs() <= f64::EPSILON * a.max(b) {
+        return h * a.max(b).powf(p);
+    }
+    h * (b.powf(p + 1.0) - a.powf(p + 1.0)) / ((p + 1.0) * (b - a))
+}
+
+/// Largest absolute difference between two levels and the x where it happens
+#[must_use]
+pub fn sup_distance(a: &[(f64,f64)], b: &[(f64,f64)]) -> (f64, f64) {
+    breakpoints(&[a, b])
+        .into_iter()
+        .map(|x| ((evaluate(a, x) - evaluate(b, x)).abs(), x))
+        .fold((0.0, f64::NAN), |best, d| if d.0 > best.0 || best.1.is_nan() { d } else { best })
+}
+
+fn level_pow_distance(a: &[(f64,f64)], b: &[(f64,f64)], p: f64) -> f64 {
+    let xs = breakpoints(&[a, b]);
+    xs.windows(2)
+        .map(|w| {
+            let d0 = evaluate(a, w[0]) - evaluate(b, w[0]);
+            let d1 = evaluate(a, w[1]) - evaluate(b, w[1]);
+            segment_abs_pow(d0, d1, w[1] - w[0], p)
+        })
+        .sum()
+}
+
+pub(crate) fn check_order(p: f64) -> Result<(), Error> {
+    if p >= 1.0 {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument(format!("Lp distance requires p >= 1, got {p}")))
+    }
+}
+
+// `lp_distance` for callers that already checked `p`
+pub(crate) fn lp_distance_unchecked(a: &[Vec<(f64,f64)>], b: &[Vec<(f64,f64)>], p: f64) -> f64 {
+    let k = a.len().max(b.len());
+    if p.is_infinite() {
+        return (0..k)
+            .map(|i| sup_distance(level(a, i), level(b, i)).0)
+            .fold(0.0, f64::max);
+    }
+    (0..k)
+        .map(|i| level_pow_distance(level(a, i), level(b, i), p))
+        .sum::<f64>()
+        .powf(1.0 / p)
+}
+
+/// Exact Lp distance between two landscapes, summing over all levels. `p` may be
+/// `f64::INFINITY` for the sup-norm.
+///
+/// # Errors
+///
+/// Will return 'Err' if `p` is less than 1
+pub fn lp_distance(a: &[Vec<(f64,f64)>], b: &[Vec<(f64,f64)>], p: f64) -> Result<f64, Error> {
+    check_order(p)?;
+    Ok(lp_distance_unchecked(a, b, p))
+}
+
+/// Exact Lp norm of a landscape, summing over all levels
+///
+/// # Errors
+///
+/// Will return 'Err' if `p` is less than 1
+pub fn lp_norm(landscape: &[Vec<(f64,f64)>], p: f64) -> Result<f64, Error> {
+    lp_distance(landscape, &[], p)
+}
+
+fn level_inner_product(a: &[(f64,f64)], b: &[(f64,f64)]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    breakpoints(&[a, b])
+        .windows(2)
+        .map(|w| {
+            let (a0, a1) = (evaluate(a, w[0]), evaluate(a, w[1]));
+            let (b0, b1) = (evaluate(b, w[0]), evaluate(b, w[1]));
+            (w[1] - w[0]) * (2.0 * a0).mul_add(b0, (2.0 * a1).mul_add(b1, a0.mul_add(b1, a1 * b0))) / 6.0
+        })
+        .sum()
+}
+
+/// Exact L2 inner product of two landscapes, summing over all levels
+#[must_use]
+pub fn inner_product(a: &[Vec<(f64,f64)>], b: &[Vec<(f64,f64)>]) -> f64 {
+    (0..a.len().min(b.len()))
+        .map(|i| level_inner_product(&a[i], &b[i]))
+        .sum()
+}
@@ -8,20 +8,18 @@
 use float_ord::FloatOrd;
 use plotters::prelude::*;
 
-/// # Panics
-///
-/// Will panic on fail to generate chart
-/// Will panic on fail to draw chart
-/// Will panic on fail to add data to chart
+use crate::error::Error;
+
+/// Draw every level of `landscape` to output.png
 ///
 /// # Errors
 ///
-/// Will error and propogate up on same conditions as panics
+/// Will return 'Err' if the chart can not be set up, drawn or written to output.png
 pub fn landscape(
     landscape: Vec<Vec<(f64,f64)>>,
     height: u32,
     width: u32,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), Error> {
     // Set up the data
     let to_plot: Vec<Vec<(f64, f64)>> = landscape
         .into_iter()
@@ -41,12 +39,7 @@ pub fn landscape(
                bounds.3.max(y),
                ));
     let root = BitMapBackend::new("output.png", (width, height)).into_drawing_area();
-    match root.fill(&WHITE) {
-        Ok(_) => (),
-        _ => {
-            unreachable!("Could not set backgrond color")
-        }
-    };
+    root.fill(&WHITE)?;
     let root = root.margin(10, 10, 10, 10);
     // After this point, we should be able to draw construct a chart context
     let mut chart = ChartBuilder::on(&root)
@@ -66,7 +59,7 @@ pub fn landscape(
         .y_label_formatter(&|x| format!("{x:.3}"))
         .draw()?;
 
-    let colors = vec![&RED, &GREEN, &BLUE];
+    let colors = [&RED, &GREEN, &BLUE];
     for (i, data) in to_plot.into_iter().enumerate() {
         chart
             .draw_series(LineSeries::new(data, colors[i % colors.len()]))?;
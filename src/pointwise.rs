@@ -0,0 +1,128 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use crate::error::Error;
+use crate::piecewiselinear;
+
+// Between two consecutive breakpoints every level is linear, so the order of the levels can only
+// change where two of them cross. Adding all crossings as breakpoints makes any order statistic
+// linear between breakpoints and therefore exact.
+fn crossings(levels: &[&[(f64,f64)]], x0: f64, x1: f64) -> Vec<f64> {
+    let start: Vec<f64> = levels.iter().map(|l| piecewiselinear::evaluate(l, x0)).collect();
+    let end: Vec<f64> = levels.iter().map(|l| piecewiselinear::evaluate(l, x1)).collect();
+    let mut found = Vec::new();
+    for i in 0..levels.len() {
+        for j in (i + 1)..levels.len() {
+            let d0 = start[i] - start[j];
+            let d1 = end[i] - end[j];
+            if (d0 < 0.0 && d1 > 0.0) || (d0 > 0.0 && d1 < 0.0) {
+                let x = x0 + (x1 - x0) * d0 / (d0 - d1);
+                if x > x0 && x < x1 {
+                    found.push(x);
+                }
+            }
+        }
+    }
+    found
+}
+
+fn pointwise_level<F>(levels: &[&[(f64,f64)]], statistic: F) -> Vec<(f64,f64)>
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let base = piecewiselinear::breakpoints(levels);
+    let mut xs = base.clone();
+    for w in base.windows(2) {
+        xs.extend(crossings(levels, w[0], w[1]));
+    }
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+
+    let mut values = vec![0.0; levels.len()];
+    xs.into_iter()
+        .map(|x| {
+            for (v, level) in values.iter_mut().zip(levels) {
+                *v = piecewiselinear::evaluate(level, x);
+            }
+            values.sort_by(f64::total_cmp);
+            (x, statistic(&values))
+        })
+        .collect()
+}
+
+fn pointwise<F>(landscapes: &[Vec<Vec<(f64,f64)>>], statistic: F) -> Vec<Vec<(f64,f64)>>
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let k = landscapes.iter().map(Vec::len).max().unwrap_or(0);
+    (0..k)
+        .map(|level| {
+            let levels: Vec<&[(f64,f64)]> = landscapes
+                .iter()
+                .map(|l| piecewiselinear::level(l, level))
+                .collect();
+            pointwise_level(&levels, &statistic)
+        })
+        .collect()
+}
+
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub(crate) fn sorted_quantile(sorted: &[f64], q: f64) -> f64 {
+    let h = (sorted.len() - 1) as f64 * q;
+    let lo = h.floor() as usize;
+    if lo + 1 >= sorted.len() {
+        return sorted[sorted.len() - 1];
+    }
+    (h - lo as f64).mul_add(sorted[lo + 1] - sorted[lo], sorted[lo])
+}
+
+/// Pointwise `q` quantile of each level, interpolating linearly between order statistics
+///
+/// # Errors
+///
+/// Will return 'Err' if `q` is not in [0, 1]
+pub fn quantile(landscapes: &[Vec<Vec<(f64,f64)>>], q: f64) -> Result<Vec<Vec<(f64,f64)>>, Error> {
+    if !(0.0..=1.0).contains(&q) {
+        return Err(Error::InvalidArgument(format!("Quantile {q} is not in [0, 1]")));
+    }
+    Ok(pointwise(landscapes, |sorted| sorted_quantile(sorted, q)))
+}
+
+/// Pointwise median of each level
+#[must_use]
+pub fn median(landscapes: &[Vec<Vec<(f64,f64)>>]) -> Vec<Vec<(f64,f64)>> {
+    pointwise(landscapes, |sorted| sorted_quantile(sorted, 0.5))
+}
+
+/// Pointwise mean of each level after dropping the lowest and highest `trim` fraction of values
+///
+/// # Errors
+///
+/// Will return 'Err' if `trim` is not in [0, 0.5)
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn trimmed_mean(landscapes: &[Vec<Vec<(f64,f64)>>], trim: f64) -> Result<Vec<Vec<(f64,f64)>>, Error> {
+    if !(0.0..0.5).contains(&trim) {
+        return Err(Error::InvalidArgument(format!("Trim fraction {trim} is not in [0, 0.5)")));
+    }
+    let cut = (landscapes.len() as f64 * trim).floor() as usize;
+    Ok(pointwise(landscapes, |sorted| {
+        let kept = &sorted[cut..sorted.len() - cut];
+        kept.iter().sum::<f64>() / kept.len() as f64
+    }))
+}
+
+/// Pointwise minimum of each level
+#[must_use]
+pub fn min_envelope(landscapes: &[Vec<Vec<(f64,f64)>>]) -> Vec<Vec<(f64,f64)>> {
+    pointwise(landscapes, |sorted| sorted[0])
+}
+
+/// Pointwise maximum of each level
+#[must_use]
+pub fn max_envelope(landscapes: &[Vec<Vec<(f64,f64)>>]) -> Vec<Vec<(f64,f64)>> {
+    pointwise(landscapes, |sorted| sorted[sorted.len() - 1])
+}
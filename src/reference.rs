@@ -0,0 +1,81 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+// Brute force persistence landscapes. Far too slow for real diagrams, this exists so the sweep in
+// `persistencelandscape` can be checked against something that is obviously right.
+
+use crate::birthdeath::{self, BirthDeath};
+use crate::error::Error;
+
+fn tent(pair: &BirthDeath, x: f64) -> f64 {
+    (x - pair.birth).min(pair.death - x).max(0.0)
+}
+
+fn finite(bd_pairs: &[BirthDeath]) -> impl Iterator<Item = &BirthDeath> {
+    bd_pairs.iter().filter(|bd| bd.birth.is_finite() && bd.death.is_finite())
+}
+
+/// Value of level `k` (counted from 0) at `x`, the kth largest tent at `x`
+#[must_use]
+pub fn evaluate(bd_pairs: &[BirthDeath], k: usize, x: f64) -> f64 {
+    let mut values: Vec<f64> = finite(bd_pairs).map(|bd| tent(bd, x)).collect();
+    values.sort_by(|a, b| b.total_cmp(a));
+    values.get(k).copied().unwrap_or(0.0)
+}
+
+/// Every x where a level can have a vertex: births, deaths, peaks and the points where a rising
+/// side crosses a falling one, sorted without duplicates
+#[must_use]
+pub fn candidates(bd_pairs: &[BirthDeath]) -> Vec<f64> {
+    let pairs: Vec<&BirthDeath> = finite(bd_pairs).collect();
+    let mut xs: Vec<f64> = pairs
+        .iter()
+        .flat_map(|a| pairs.iter().map(|b| (a.birth + b.death) / 2.0))
+        .chain(pairs.iter().flat_map(|bd| [bd.birth, bd.death]))
+        .collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    xs
+}
+
+// Drop zero vertices whose neighbours are zero too, the level is zero there anyway
+fn trim_zeros(values: &[(f64,f64)]) -> Vec<(f64,f64)> {
+    let zero = |i: Option<usize>| i.and_then(|i| values.get(i)).is_none_or(|p| p.1 == 0.0);
+    let level: Vec<(f64,f64)> = values
+        .iter()
+        .enumerate()
+        .filter(|&(i, p)| p.1 != 0.0 || !zero(i.checked_sub(1)) || !zero(Some(i + 1)))
+        .map(|(_, &p)| p)
+        .collect();
+    if level.iter().all(|p| p.1 == 0.0) {
+        return Vec::new();
+    }
+    level
+}
+
+/// The first `k` levels of the landscape of `bd_pairs` by evaluating every tent at every
+/// `candidates` x
+///
+/// The levels have the same values as `persistencelandscape::generate` but not necessarily the
+/// same vertices, compare them as functions. Takes O(n^3 log n) time.
+///
+/// # Errors
+///
+/// Will return 'Err' if a pair has a NaN coordinate or dies before it is born
+pub fn generate(bd_pairs: &[BirthDeath], k: usize) -> Result<Vec<Vec<(f64,f64)>>, Error> {
+    birthdeath::check_pairs(bd_pairs)?;
+    let xs = candidates(bd_pairs);
+    let mut levels = vec![Vec::with_capacity(xs.len()); k];
+    for &x in &xs {
+        let mut values: Vec<f64> = finite(bd_pairs).map(|bd| tent(bd, x)).collect();
+        values.sort_by(|a, b| b.total_cmp(a));
+        for (i, level) in levels.iter_mut().enumerate() {
+            level.push((x, values.get(i).copied().unwrap_or(0.0)));
+        }
+    }
+    Ok(levels.iter().map(|level| trim_zeros(level)).collect())
+}
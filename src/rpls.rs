@@ -8,20 +8,26 @@
 use std::cmp;
 use float_ord::FloatOrd;
 
-use crate::birthdeath::BirthDeath;
+use crate::birthdeath::{self, BirthDeath};
+use crate::error::Error;
 use crate::persistencelandscape;
 use crate::barcode;
+use crate::silhouette;
+use crate::tolerance::Tolerance;
 
+/// Landscape of `bd_pairs` after dropping pairs whose birth and death are the same under
+/// `tolerance`
+///
 /// # Errors
 ///
 /// Will return 'Err' if failed to compute persistencelandscape from `bd_pairs`
-pub fn pairs_to_landscape(bd_pairs: Vec<BirthDeath>, k:usize, debug:bool, disable_filter: bool) -> Result<Vec<Vec<(f64,f64)>>, &'static str>{
+pub fn pairs_to_landscape(bd_pairs: Vec<BirthDeath>, k:usize, debug:bool, disable_filter: bool, tolerance: Tolerance) -> Result<Vec<Vec<(f64,f64)>>, Error>{
     let bd_pairs: Vec<BirthDeath> = bd_pairs
         .into_iter()
-        .filter(|bd| (bd.birth - bd.death).abs() > f64::EPSILON)
+        .filter(|bd| !tolerance.approx_eq(bd.birth, bd.death))
         .collect();
     if bd_pairs.is_empty() {
-        return Err("No BirthDeath pairs found in file");
+        return Err(Error::EmptyInput);
     }
 
     if debug {
@@ -31,65 +37,98 @@ pub fn pairs_to_landscape(bd_pairs: Vec<BirthDeath>, k:usize, debug:bool, disabl
         bd_pairs
     }
     else{
-        let filtered_pairs = barcode::filter(bd_pairs, k);
+        let filtered_pairs = barcode::filter(bd_pairs, k)?;
         if debug {
             println!("{filtered_pairs:?}");
         }
         filtered_pairs
     };
-    let landscape = persistencelandscape::generate(filtered_pairs, k, debug);
+    let landscape = persistencelandscape::generate_with_tolerance(filtered_pairs, k, debug, tolerance)?;
     if debug {
         println!("{landscape:?}");
     }
     Ok(landscape)
 }
 
-fn area_under_line_segment(a: (f64,f64), b: (f64,f64)) ->f64 {
+/// Power weighted silhouette of `bd_pairs`
+///
+/// `sum_j w_j L_j(t) / sum_j w_j` where the `L_j` are the landscape tents and
+/// `w_j = (death - birth)^p`. Pairs of zero persistence under `tolerance` are dropped first.
+///
+/// # Errors
+///
+/// Will return 'Err' if a pair is invalid or no pair has positive persistence
+pub fn pairs_to_silhouette(bd_pairs: Vec<BirthDeath>, p: f64, debug:bool, tolerance: Tolerance) -> Result<Vec<(f64,f64)>, Error>{
+    birthdeath::check_pairs(&bd_pairs)?;
+    let bd_pairs: Vec<BirthDeath> = bd_pairs
+        .into_iter()
+        .filter(|bd| !tolerance.approx_eq(bd.birth, bd.death))
+        .collect();
+    if bd_pairs.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+
+    if debug {
+        println!("{bd_pairs:?}");
+    }
+    let silhouette = silhouette::generate(bd_pairs, p);
+    if debug {
+        println!("{silhouette:?}");
+    }
+    Ok(silhouette)
+}
+
+fn area_under_line_segment(a: (f64,f64), b: (f64,f64)) -> Result<f64, Error> {
+    let base = b.0 - a.0;
+    if base < 0.0 {
+        return Err(Error::InvalidArgument(format!("Landscape x decreases from {} to {}", a.0, b.0)));
+    }
     let height = (a.1 - b.1).abs();
-    let base = a.0 - b.0;
     let triangle = (height * base) / 2.0;
-    assert!(triangle > 0.0);
 
     let s1 = base;
     let s2 = cmp::min(FloatOrd(a.1), FloatOrd(b.1)).0;
     let rectangle = s1 * s2;
-    assert!(rectangle >= 0.0);
+    if rectangle < 0.0 {
+        return Err(Error::InvalidArgument(format!("Landscape is negative between {} and {}", a.0, b.0)));
+    }
 
-    triangle + rectangle
+    Ok(triangle + rectangle)
 }
 
-fn landscape_norm(landscape: &[(f64,f64)]) -> f64 {
+fn landscape_norm(landscape: &[(f64,f64)]) -> Result<f64, Error> {
     landscape
         .iter()
         .zip(landscape.iter().skip(1))
         .map(|(a, b)| area_under_line_segment(*a, *b))
-        .sum::<f64>()
+        .sum()
 }
 
-fn is_sorted<T>(data: &[T]) -> bool
+fn is_decreasing<T>(data: &[T]) -> bool
 where
     T: Ord,
 {
-    data.windows(2).all(|w| w[0] <= w[1])
+    data.windows(2).all(|w| w[0] >= w[1])
 }
 
-/// # Panics
+/// # Errors
 ///
-/// Will panic if areas are not strictly decreasing or equal
-#[must_use]
-pub fn l2_norm(landscapes: &[Vec<(f64,f64)>]) -> f64 {
+/// Will return 'Err' if areas are not strictly decreasing or equal
+pub fn l2_norm(landscapes: &[Vec<(f64,f64)>]) -> Result<f64, Error> {
     let areas = landscapes
         .iter()
-        .map(|l| FloatOrd(landscape_norm(l)))
-        .collect::<Vec<FloatOrd<f64>>>();
-    assert!(is_sorted(& areas));
+        .map(|l| landscape_norm(l).map(FloatOrd))
+        .collect::<Result<Vec<FloatOrd<f64>>, Error>>()?;
+    if !is_decreasing(& areas) {
+        return Err(Error::InvalidArgument("Landscape areas are not decreasing".to_string()));
+    }
 
-        areas.iter().map(|x| x.0).sum()
+        Ok(areas.iter().map(|x| x.0).sum())
 }
 
 /// # Errors
 ///
 /// Will return 'Err' if failed to compute persistencelandscape from `bd_pairs`
-pub fn pairs_to_l2_norm(bd_paris: Vec<BirthDeath>, k:usize, debug:bool, disable_filter: bool) -> Result<f64, &'static str>{
-    Ok(l2_norm(pairs_to_landscape(bd_paris, k, debug, disable_filter)?.as_slice()))
+pub fn pairs_to_l2_norm(bd_paris: Vec<BirthDeath>, k:usize, debug:bool, disable_filter: bool, tolerance: Tolerance) -> Result<f64, Error>{
+    l2_norm(pairs_to_landscape(bd_paris, k, debug, disable_filter, tolerance)?.as_slice())
 }
@@ -0,0 +1,57 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use float_ord::FloatOrd;
+
+use crate::birthdeath::BirthDeath;
+use crate::persistencelandscape::{self, PersistenceMountain};
+
+fn weight(mountain: &PersistenceMountain, p: f64) -> f64 {
+    (mountain.death.x.0 - mountain.birth.x.0).powf(p)
+}
+
+// Power weighted silhouette of `bd_pairs`, which must already be valid and free of zero
+// persistence pairs, `rpls::pairs_to_silhouette` is the public entry point
+//
+// Computes `sum_j w_j L_j(t) / sum_j w_j` with `w_j = (death - birth)^p` where `L_j` are the same
+// tents the landscape is built from. The silhouette only changes slope at births, deaths and tent
+// peaks so it is found exactly by sweeping over those points.
+pub(crate) fn generate(bd_pairs: Vec<BirthDeath>, p: f64) -> Vec<(f64,f64)> {
+    let mountains = persistencelandscape::generate_mountains(bd_pairs);
+    let mut total = 0.0;
+    let mut slope_changes: Vec<(f64, f64)> = Vec::with_capacity(3 * mountains.len());
+    for m in &mountains {
+        let w = weight(m, p);
+        total += w;
+        slope_changes.push((m.birth.x.0, w));
+        slope_changes.push((m.middle.x.0, -2.0 * w));
+        slope_changes.push((m.death.x.0, w));
+    }
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    slope_changes.sort_by_key(|(x, _)| FloatOrd(*x));
+
+    let mut silhouette: Vec<(f64,f64)> = Vec::new();
+    let mut slope: f64 = 0.0;
+    let mut value: f64 = 0.0;
+    for (i, (x, change)) in slope_changes.iter().enumerate() {
+        if let Some(&(last_x, _)) = silhouette.last() {
+            value = slope.mul_add(x - last_x, value);
+        }
+        slope += change / total;
+        if i + 1 == slope_changes.len() {
+            // Every tent has died
+            value = 0.0;
+        }
+        match silhouette.last_mut() {
+            Some(last) if FloatOrd(last.0) == FloatOrd(*x) => last.1 = value.max(0.0),
+            _ => silhouette.push((*x, value.max(0.0))),
+        }
+    }
+    silhouette
+}
@@ -0,0 +1,147 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use crate::birthdeath::BirthDeath;
+use crate::pointwise::sorted_quantile;
+
+/// Summary of a single diagram. Persistence and death statistics only use finite pairs and are
+/// NaN when there are none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramStats {
+    pub count: usize,
+    pub finite: usize,
+    pub infinite: usize,
+    pub persistence_min: f64,
+    pub persistence_q25: f64,
+    pub persistence_median: f64,
+    pub persistence_mean: f64,
+    pub persistence_q75: f64,
+    pub persistence_max: f64,
+    /// Order p used for `total_persistence`
+    pub order: f64,
+    /// Sum of persistence^p
+    pub total_persistence: f64,
+    /// Shannon entropy of the normalised persistences
+    pub entropy: f64,
+    pub birth_min: f64,
+    pub birth_max: f64,
+    pub death_min: f64,
+    pub death_max: f64,
+}
+
+const FIELDS: [&str; 16] = [
+    "count",
+    "finite",
+    "infinite",
+    "persistence_min",
+    "persistence_q25",
+    "persistence_median",
+    "persistence_mean",
+    "persistence_q75",
+    "persistence_max",
+    "order",
+    "total_persistence",
+    "entropy",
+    "birth_min",
+    "birth_max",
+    "death_min",
+    "death_max",
+];
+
+fn min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::NAN, f64::NAN), |(lo, hi), v| (lo.min(v), hi.max(v)))
+}
+
+/// Compute `DiagramStats` for `bd_pairs` with total persistence of order `p`
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn summarize(bd_pairs: &[BirthDeath], p: f64) -> DiagramStats {
+    let finite: Vec<&BirthDeath> = bd_pairs
+        .iter()
+        .filter(|bd| bd.birth.is_finite() && bd.death.is_finite())
+        .collect();
+    let mut persistence: Vec<f64> = finite.iter().map(|bd| bd.persistence()).collect();
+    persistence.sort_by(f64::total_cmp);
+
+    let quantile = |q: f64| if persistence.is_empty() { f64::NAN } else { sorted_quantile(&persistence, q) };
+    let total: f64 = persistence.iter().sum();
+    let entropy = -persistence
+        .iter()
+        .filter(|&&l| l > 0.0)
+        .map(|l| l / total)
+        .map(|q| q * q.ln())
+        .sum::<f64>();
+    let (birth_min, birth_max) = min_max(bd_pairs.iter().map(|bd| bd.birth).filter(|b| b.is_finite()));
+    let (death_min, death_max) = min_max(finite.iter().map(|bd| bd.death));
+
+    DiagramStats {
+        count: bd_pairs.len(),
+        finite: finite.len(),
+        infinite: bd_pairs.len() - finite.len(),
+        persistence_min: quantile(0.0),
+        persistence_q25: quantile(0.25),
+        persistence_median: quantile(0.5),
+        persistence_mean: if persistence.is_empty() { f64::NAN } else { total / persistence.len() as f64 },
+        persistence_q75: quantile(0.75),
+        persistence_max: quantile(1.0),
+        order: p,
+        total_persistence: persistence.iter().map(|l| l.powf(p)).sum(),
+        entropy: if total > 0.0 { entropy } else { f64::NAN },
+        birth_min,
+        birth_max,
+        death_min,
+        death_max,
+    }
+}
+
+impl DiagramStats {
+    /// Field names in the order of `values`
+    #[must_use]
+    pub const fn fields() -> [&'static str; 16] {
+        FIELDS
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn values(&self) -> [f64; 16] {
+        [
+            self.count as f64,
+            self.finite as f64,
+            self.infinite as f64,
+            self.persistence_min,
+            self.persistence_q25,
+            self.persistence_median,
+            self.persistence_mean,
+            self.persistence_q75,
+            self.persistence_max,
+            self.order,
+            self.total_persistence,
+            self.entropy,
+            self.birth_min,
+            self.birth_max,
+            self.death_min,
+            self.death_max,
+        ]
+    }
+
+    /// Single line JSON object, values that are not finite are written as null
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let body: Vec<String> = FIELDS
+            .iter()
+            .zip(self.values())
+            .map(|(name, v)| {
+                if v.is_finite() {
+                    format!("\"{name}\": {v}")
+                } else {
+                    format!("\"{name}\": null")
+                }
+            })
+            .collect();
+        format!("{{{}}}", body.join(", "))
+    }
+}
@@ -0,0 +1,96 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use crate::error::Error;
+use crate::piecewiselinear;
+
+// A step function is a list of (x, value) in increasing x. The value holds on [x, next x) and the
+// function is the default value (zero) before the first step.
+
+/// Value of a step function at `x`
+#[must_use]
+pub fn evaluate<T: Copy + Default>(steps: &[(f64, T)], x: f64) -> T {
+    let i = steps.partition_point(|s| s.0 <= x);
+    if i == 0 {
+        T::default()
+    } else {
+        steps[i - 1].1
+    }
+}
+
+/// Values of a step function at every point of `grid`
+#[must_use]
+pub fn sample<T: Copy + Default>(steps: &[(f64, T)], grid: &[f64]) -> Vec<T> {
+    grid.iter().map(|&x| evaluate(steps, x)).collect()
+}
+
+/// `n` evenly spaced points from `start` to `stop` inclusive
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn linspace(start: f64, stop: f64, n: usize) -> Vec<f64> {
+    match n {
+        0 => Vec::new(),
+        1 => vec![start],
+        _ => {
+            let spacing = (stop - start) / (n - 1) as f64;
+            (0..n)
+                .map(|i| if i == n - 1 { stop } else { (i as f64).mul_add(spacing, start) })
+                .collect()
+        }
+    }
+}
+
+// Lp norm of the difference of two step functions over the breakpoints `xs`, the last one only
+// closes the final interval
+fn integrate(a: &[(f64, f64)], b: &[(f64, f64)], xs: &[f64], p: f64) -> f64 {
+    let difference = |x: f64| (evaluate(a, x) - evaluate(b, x)).abs();
+    if p.is_infinite() {
+        return xs.iter().rev().skip(1).map(|&x| difference(x)).fold(0.0, f64::max);
+    }
+    xs.windows(2)
+        .map(|w| (w[1] - w[0]) * difference(w[0]).powf(p))
+        .sum::<f64>()
+        .powf(1.0 / p)
+}
+
+/// Exact Lp distance between two step functions, `p` may be `f64::INFINITY` for the sup-norm.
+/// The distance is infinite if the functions differ after their last step.
+///
+/// # Errors
+///
+/// Will return 'Err' if `p` is less than 1
+pub fn lp_distance(a: &[(f64, f64)], b: &[(f64, f64)], p: f64) -> Result<f64, Error> {
+    piecewiselinear::check_order(p)?;
+    let mut xs: Vec<f64> = a.iter().chain(b).map(|s| s.0).collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    let Some(&last) = xs.last() else {
+        return Ok(0.0);
+    };
+    let after = (evaluate(a, last) - evaluate(b, last)).abs();
+    if p.is_infinite() {
+        return Ok(integrate(a, b, &xs, p).max(after));
+    }
+    if after > 0.0 {
+        return Ok(f64::INFINITY);
+    }
+    Ok(integrate(a, b, &xs, p))
+}
+
+/// Lp distance between two step functions on x < `end`, finite even if the functions differ after
+/// their last step. Steps at or after `end` are ignored.
+///
+/// # Errors
+///
+/// Will return 'Err' if `p` is less than 1
+pub fn truncated_lp_distance(a: &[(f64, f64)], b: &[(f64, f64)], p: f64, end: f64) -> Result<f64, Error> {
+    piecewiselinear::check_order(p)?;
+    let mut xs: Vec<f64> = a.iter().chain(b).map(|s| s.0).filter(|&x| x < end).chain([end]).collect();
+    xs.sort_by(f64::total_cmp);
+    xs.dedup();
+    Ok(integrate(a, b, &xs, p))
+}
@@ -0,0 +1,358 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::betticurve;
+use crate::birthdeath::BirthDeath;
+use crate::error::Error;
+use crate::lifetime;
+use crate::persistenceimage::{self, PersistenceImageConfig};
+use crate::piecewiselinear;
+use crate::rpls;
+use crate::stepfunction;
+use crate::tolerance::Tolerance;
+
+/// Settings shared by every summary, each summary only reads the ones it needs
+#[derive(Debug, Clone, Copy)]
+pub struct SummaryOptions {
+    /// Max kth-landscape to calculate
+    pub k: usize,
+    /// Disables filtering of pairs that can not reach the kth landscape
+    pub disable_filter: bool,
+    /// Power of the persistence weights of the silhouette
+    pub power: f64,
+    pub image: PersistenceImageConfig,
+    /// When a birth and a death are the same, and when landscape vertices merge
+    pub tolerance: Tolerance,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        Self {
+            k: 1,
+            disable_filter: false,
+            power: 1.0,
+            image: PersistenceImageConfig::default(),
+            tolerance: Tolerance::default(),
+        }
+    }
+}
+
+/// A functional summary of a persistence diagram
+pub trait Summary: Sized {
+    /// Summary of `bd_pairs`
+    ///
+    /// # Errors
+    ///
+    /// Will return 'Err' if the summary can not be computed from `bd_pairs`
+    fn compute(bd_pairs: Vec<BirthDeath>, options: &SummaryOptions) -> Result<Self, Error>;
+
+    /// Values of the summary at `t`, one per component (landscape level, image row)
+    fn evaluate(&self, t: f64) -> Vec<f64>;
+
+    /// Fixed length feature vector, `evaluate` at every point of `grid` one after the other
+    fn vectorize(&self, grid: &[f64]) -> Vec<f64> {
+        grid.iter().flat_map(|&t| self.evaluate(t)).collect()
+    }
+
+    /// Lp norm, `p` may be `f64::INFINITY`
+    ///
+    /// # Errors
+    ///
+    /// Will return 'Err' if `p` is less than 1
+    fn norm(&self, p: f64) -> Result<f64, Error>;
+
+    /// Lp distance to a summary of the same kind, `p` may be `f64::INFINITY`
+    ///
+    /// # Errors
+    ///
+    /// Will return 'Err' if `p` is less than 1
+    fn distance(&self, other: &Self, p: f64) -> Result<f64, Error>;
+}
+
+/// Persistence landscape, see `persistencelandscape::generate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Landscape(pub Vec<Vec<(f64,f64)>>);
+
+/// Power weighted silhouette, see `rpls::pairs_to_silhouette`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Silhouette(pub Vec<(f64,f64)>);
+
+/// Betti curve as a step function, see `betticurve::betti_curve`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BettiCurve {
+    pub steps: Vec<(f64,f64)>,
+    pub end: f64,
+}
+
+/// Accumulated persistence function, see `lifetime::accumulated_persistence`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccumulatedPersistence {
+    pub steps: Vec<(f64,f64)>,
+    pub end: f64,
+}
+
+/// Cumulative lifetime curve, see `lifetime::cumulative_lifetime`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CumulativeLifetime {
+    pub steps: Vec<(f64,f64)>,
+    pub end: f64,
+}
+
+/// Persistence image together with the birth range it covers, see `persistenceimage::generate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistenceImage {
+    pub pixels: Vec<Vec<f64>>,
+    pub birth_range: (f64, f64),
+}
+
+impl Summary for Landscape {
+    fn compute(bd_pairs: Vec<BirthDeath>, options: &SummaryOptions) -> Result<Self, Error> {
+        rpls::pairs_to_landscape(bd_pairs, options.k, false, options.disable_filter, options.tolerance).map(Self)
+    }
+
+    fn evaluate(&self, t: f64) -> Vec<f64> {
+        self.0.iter().map(|level| piecewiselinear::evaluate(level, t)).collect()
+    }
+
+    fn norm(&self, p: f64) -> Result<f64, Error> {
+        piecewiselinear::lp_norm(&self.0, p)
+    }
+
+    fn distance(&self, other: &Self, p: f64) -> Result<f64, Error> {
+        piecewiselinear::lp_distance(&self.0, &other.0, p)
+    }
+}
+
+impl Summary for Silhouette {
+    fn compute(bd_pairs: Vec<BirthDeath>, options: &SummaryOptions) -> Result<Self, Error> {
+        rpls::pairs_to_silhouette(bd_pairs, options.power, false, options.tolerance).map(Self)
+    }
+
+    fn evaluate(&self, t: f64) -> Vec<f64> {
+        vec![piecewiselinear::evaluate(&self.0, t)]
+    }
+
+    fn norm(&self, p: f64) -> Result<f64, Error> {
+        piecewiselinear::lp_norm(std::slice::from_ref(&self.0), p)
+    }
+
+    fn distance(&self, other: &Self, p: f64) -> Result<f64, Error> {
+        piecewiselinear::lp_distance(std::slice::from_ref(&self.0), std::slice::from_ref(&other.0), p)
+    }
+}
+
+// The step function summaries only differ in how they are computed. A step function need not
+// return to zero, so norms and distances are taken up to `end`, see `finite_end`.
+macro_rules! step_summary {
+    ($name:ident, $compute:expr) => {
+        impl Summary for $name {
+            fn compute(bd_pairs: Vec<BirthDeath>, _options: &SummaryOptions) -> Result<Self, Error> {
+                Ok(Self {
+                    steps: $compute(&bd_pairs),
+                    end: finite_end(&bd_pairs),
+                })
+            }
+
+            fn evaluate(&self, t: f64) -> Vec<f64> {
+                vec![stepfunction::evaluate(&self.steps, t)]
+            }
+
+            fn norm(&self, p: f64) -> Result<f64, Error> {
+                stepfunction::truncated_lp_distance(&self.steps, &[], p, self.end)
+            }
+
+            fn distance(&self, other: &Self, p: f64) -> Result<f64, Error> {
+                stepfunction::truncated_lp_distance(&self.steps, &other.steps, p, self.end.max(other.end))
+            }
+        }
+    };
+}
+
+// Largest finite birth or death, every step of the step summaries is at or before it
+fn finite_end(bd_pairs: &[BirthDeath]) -> f64 {
+    bd_pairs
+        .iter()
+        .flat_map(|bd| [bd.birth, bd.death])
+        .filter(|x| x.is_finite())
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn betti_steps(bd_pairs: &[BirthDeath]) -> Vec<(f64,f64)> {
+    betticurve::betti_curve(bd_pairs)
+        .into_iter()
+        .map(|(x, count)| (x, count as f64))
+        .collect()
+}
+
+step_summary!(BettiCurve, betti_steps);
+step_summary!(AccumulatedPersistence, lifetime::accumulated_persistence);
+step_summary!(CumulativeLifetime, lifetime::cumulative_lifetime);
+
+fn pixel_distance(a: &[Vec<f64>], b: &[Vec<f64>], p: f64) -> Result<f64, Error> {
+    piecewiselinear::check_order(p)?;
+    let differences = a
+        .iter()
+        .zip(b)
+        .flat_map(|(ra, rb)| ra.iter().zip(rb).map(|(x, y)| (x - y).abs()));
+    if p.is_infinite() {
+        return Ok(differences.fold(0.0, f64::max));
+    }
+    Ok(differences.map(|d| d.powf(p)).sum::<f64>().powf(1.0 / p))
+}
+
+impl Summary for PersistenceImage {
+    fn compute(bd_pairs: Vec<BirthDeath>, options: &SummaryOptions) -> Result<Self, Error> {
+        let (birth_range, _) = persistenceimage::bounds(&bd_pairs, &options.image);
+        Ok(Self {
+            pixels: persistenceimage::generate(bd_pairs, &options.image)?,
+            birth_range,
+        })
+    }
+
+    /// Column of pixels whose birth interval holds `t`, zero outside of the image
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn evaluate(&self, t: f64) -> Vec<f64> {
+        let (lo, hi) = self.birth_range;
+        let nb = self.pixels.first().map_or(0, Vec::len);
+        if t < lo || t > hi || nb == 0 {
+            return vec![0.0; self.pixels.len()];
+        }
+        let j = (((t - lo) / (hi - lo)) * nb as f64).floor() as usize;
+        self.pixels.iter().map(|row| row[j.min(nb - 1)]).collect()
+    }
+
+    /// The pixels row by row, the resolution is already fixed so `grid` is ignored
+    fn vectorize(&self, _grid: &[f64]) -> Vec<f64> {
+        self.pixels.iter().flatten().copied().collect()
+    }
+
+    fn norm(&self, p: f64) -> Result<f64, Error> {
+        pixel_distance(&self.pixels, &vec![vec![0.0; self.pixels.first().map_or(0, Vec::len)]; self.pixels.len()], p)
+    }
+
+    /// Lp distance between the pixels, only meaningful if both images were computed with the same
+    /// `bounds`
+    fn distance(&self, other: &Self, p: f64) -> Result<f64, Error> {
+        pixel_distance(&self.pixels, &other.pixels, p)
+    }
+}
+
+/// Every summary by name, used to switch between them at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryKind {
+    Landscape,
+    Silhouette,
+    BettiCurve,
+    AccumulatedPersistence,
+    CumulativeLifetime,
+    PersistenceImage,
+}
+
+impl SummaryKind {
+    pub const ALL: [Self; 6] = [
+        Self::Landscape,
+        Self::Silhouette,
+        Self::BettiCurve,
+        Self::AccumulatedPersistence,
+        Self::CumulativeLifetime,
+        Self::PersistenceImage,
+    ];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Landscape => "landscape",
+            Self::Silhouette => "silhouette",
+            Self::BettiCurve => "betti",
+            Self::AccumulatedPersistence => "apf",
+            Self::CumulativeLifetime => "lifetime",
+            Self::PersistenceImage => "image",
+        }
+    }
+
+    // Options with the image bounds fixed to cover every diagram, so that their images share one
+    // pixel grid
+    fn shared_options(self, diagrams: &[Vec<BirthDeath>], options: &SummaryOptions) -> SummaryOptions {
+        let mut options = *options;
+        if self == Self::PersistenceImage {
+            options.image.bounds = Some(persistenceimage::common_bounds(diagrams, &options.image));
+        }
+        options
+    }
+
+    /// Feature vector of the `self` summary of each diagram on `grid`, see `Summary::vectorize`.
+    /// Persistence images all cover `options.image.bounds`, or the range of every diagram together
+    /// if that is `None`.
+    ///
+    /// # Errors
+    ///
+    /// Will return 'Err' if the summary can not be computed from one of the diagrams
+    pub fn vectorize(self, diagrams: Vec<Vec<BirthDeath>>, options: &SummaryOptions, grid: &[f64]) -> Result<Vec<Vec<f64>>, Error> {
+        fn run<S: Summary>(bd_pairs: Vec<BirthDeath>, options: &SummaryOptions, grid: &[f64]) -> Result<Vec<f64>, Error> {
+            Ok(S::compute(bd_pairs, options)?.vectorize(grid))
+        }
+        let options = self.shared_options(&diagrams, options);
+        diagrams
+            .into_iter()
+            .map(|bd_pairs| match self {
+                Self::Landscape => run::<Landscape>(bd_pairs, &options, grid),
+                Self::Silhouette => run::<Silhouette>(bd_pairs, &options, grid),
+                Self::BettiCurve => run::<BettiCurve>(bd_pairs, &options, grid),
+                Self::AccumulatedPersistence => run::<AccumulatedPersistence>(bd_pairs, &options, grid),
+                Self::CumulativeLifetime => run::<CumulativeLifetime>(bd_pairs, &options, grid),
+                Self::PersistenceImage => run::<PersistenceImage>(bd_pairs, &options, grid),
+            })
+            .collect()
+    }
+
+    /// Lp distance between the `self` summaries of two diagrams, see `Summary::distance`.
+    /// Persistence images cover both diagrams as in `vectorize`.
+    ///
+    /// # Errors
+    ///
+    /// Will return 'Err' if the summary can not be computed from either diagram
+    pub fn distance(self, a: Vec<BirthDeath>, b: Vec<BirthDeath>, options: &SummaryOptions, p: f64) -> Result<f64, Error> {
+        fn run<S: Summary>(a: Vec<BirthDeath>, b: Vec<BirthDeath>, options: &SummaryOptions, p: f64) -> Result<f64, Error> {
+            S::compute(a, options)?.distance(&S::compute(b, options)?, p)
+        }
+        let diagrams = [a, b];
+        let options = &self.shared_options(&diagrams, options);
+        let [a, b] = diagrams;
+        match self {
+            Self::Landscape => run::<Landscape>(a, b, options, p),
+            Self::Silhouette => run::<Silhouette>(a, b, options, p),
+            Self::BettiCurve => run::<BettiCurve>(a, b, options, p),
+            Self::AccumulatedPersistence => run::<AccumulatedPersistence>(a, b, options, p),
+            Self::CumulativeLifetime => run::<CumulativeLifetime>(a, b, options, p),
+            Self::PersistenceImage => run::<PersistenceImage>(a, b, options, p),
+        }
+    }
+}
+
+impl fmt::Display for SummaryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SummaryKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                format!("Unknown summary '{s}', expected one of {}", names.join(", "))
+            })
+    }
+}
@@ -0,0 +1,97 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::fmt;
+use std::str::FromStr;
+
+/// When two filtration values are close enough to be treated as the same
+///
+/// Used to merge landscape vertices, to suppress duplicate points and to drop pairs of zero
+/// persistence. Equal values are always the same, infinite values are never close to anything else.
+///
+/// `Relative` and `Ulps` scale with the values compared, so against zero they are exact in
+/// practice: a relative fraction below one of |x| never covers x itself, and only subnormals are a
+/// few ulps from zero. Use `Absolute` where values near zero, such as landscape heights, should
+/// merge with it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    /// Closer than the given distance
+    Absolute(f64),
+    /// Closer than the given fraction of the larger magnitude, exact against zero
+    Relative(f64),
+    /// At most the given number of representable doubles apart, only subnormals are within a few
+    /// ulps of zero
+    Ulps(u64),
+}
+
+impl Tolerance {
+    /// Only equal values are the same
+    pub const EXACT: Self = Self::Absolute(0.0);
+
+    #[must_use]
+    pub fn approx_eq(self, a: f64, b: f64) -> bool {
+        if a == b {
+            return true;
+        }
+        if !a.is_finite() || !b.is_finite() {
+            return false;
+        }
+        match self {
+            Self::Absolute(epsilon) => (a - b).abs() < epsilon,
+            Self::Relative(epsilon) => (a - b).abs() < epsilon * a.abs().max(b.abs()),
+            Self::Ulps(ulps) => ordered_bits(a).abs_diff(ordered_bits(b)) <= ulps,
+        }
+    }
+}
+
+// Bits of `x` as an integer that is monotone in `x`, so neighbouring doubles differ by one
+#[allow(clippy::cast_possible_wrap)]
+const fn ordered_bits(x: f64) -> i64 {
+    let bits = x.to_bits() as i64;
+    if bits < 0 {
+        i64::MIN - bits
+    } else {
+        bits
+    }
+}
+
+/// The historical behaviour, an absolute `f64::EPSILON`
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::Absolute(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Absolute(epsilon) => write!(f, "abs:{epsilon:e}"),
+            Self::Relative(epsilon) => write!(f, "rel:{epsilon:e}"),
+            Self::Ulps(ulps) => write!(f, "ulp:{ulps}"),
+        }
+    }
+}
+
+/// Parses `abs:<distance>`, `rel:<fraction>` or `ulp:<count>`
+impl FromStr for Tolerance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid tolerance '{s}', expected abs:<distance>, rel:<fraction> or ulp:<count>");
+        let (kind, value) = s.split_once(':').ok_or_else(invalid)?;
+        let epsilon = || match value.trim().parse::<f64>() {
+            Ok(epsilon) if epsilon >= 0.0 => Ok(epsilon),
+            _ => Err(invalid()),
+        };
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "abs" => epsilon().map(Self::Absolute),
+            "rel" => epsilon().map(Self::Relative),
+            "ulp" => value.trim().parse().map(Self::Ulps).map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+}
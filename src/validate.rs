@@ -0,0 +1,111 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::fmt;
+
+use crate::piecewiselinear;
+use crate::tolerance::Tolerance;
+
+/// A property every persistence landscape has that `validate` found broken
+///
+/// Levels and vertices are counted from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// Vertex `index` of `level` has a NaN or infinite coordinate
+    NotFinite { level: usize, index: usize, point: (f64,f64) },
+    /// Vertex `index` of `level` is not to the right of the vertex before it
+    NotIncreasing { level: usize, index: usize, x: f64, previous_x: f64 },
+    /// The segment from vertex `index - 1` to `index` of `level` is steeper than 1
+    NotLipschitz { level: usize, index: usize, slope: f64 },
+    /// Vertex `index` of `level` is below zero
+    Negative { level: usize, index: usize, y: f64 },
+    /// The first or last vertex of `level` is not on zero, so the level jumps there
+    NotCompact { level: usize, index: usize, y: f64 },
+    /// `level` is below `level + 1` at `x`
+    NotOrdered { level: usize, x: f64, value: f64, next_value: f64 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFinite { level, index, point } => {
+                write!(f, "Level {level} vertex {index}: ({}, {}) is not finite", point.0, point.1)
+            }
+            Self::NotIncreasing { level, index, x, previous_x } => {
+                write!(f, "Level {level} vertex {index}: x {x} does not increase from {previous_x}")
+            }
+            Self::NotLipschitz { level, index, slope } => {
+                write!(f, "Level {level} vertex {index}: slope {slope} is steeper than 1")
+            }
+            Self::Negative { level, index, y } => write!(f, "Level {level} vertex {index}: y {y} is negative"),
+            Self::NotCompact { level, index, y } => {
+                write!(f, "Level {level} vertex {index}: end point has y {y} instead of 0")
+            }
+            Self::NotOrdered { level, x, value, next_value } => write!(
+                f,
+                "Level {level} at x {x}: {value} is below level {} with {next_value}",
+                level + 1
+            ),
+        }
+    }
+}
+
+fn check_level(level: &[(f64,f64)], i: usize, tolerance: Tolerance, violations: &mut Vec<Violation>) {
+    for (index, &point) in level.iter().enumerate() {
+        if !point.0.is_finite() || !point.1.is_finite() {
+            violations.push(Violation::NotFinite { level: i, index, point });
+            continue;
+        }
+        if point.1 < 0.0 && !tolerance.approx_eq(point.1, 0.0) {
+            violations.push(Violation::Negative { level: i, index, y: point.1 });
+        }
+        if index == 0 {
+            continue;
+        }
+        let previous = level[index - 1];
+        if point.0 <= previous.0 {
+            violations.push(Violation::NotIncreasing { level: i, index, x: point.0, previous_x: previous.0 });
+            continue;
+        }
+        let (dx, dy) = (point.0 - previous.0, (point.1 - previous.1).abs());
+        if dy > dx && !tolerance.approx_eq(dy, dx) {
+            violations.push(Violation::NotLipschitz { level: i, index, slope: (point.1 - previous.1) / dx });
+        }
+    }
+    for (index, point) in [(0, level.first()), (level.len().saturating_sub(1), level.last())] {
+        if let Some(&(_, y)) = point {
+            if !tolerance.approx_eq(y, 0.0) {
+                violations.push(Violation::NotCompact { level: i, index, y });
+            }
+        }
+    }
+}
+
+/// Every way `landscape` fails to be a persistence landscape, empty if it is one
+///
+/// Each level must have finite vertices in increasing x, slopes of at most 1 in absolute value,
+/// no negative values and start and end on zero, and no level may be above the one before it.
+/// Differences within `tolerance` are not reported. The ordering is checked at every vertex of
+/// either level, which is exact since the difference of two levels is linear in between.
+#[must_use]
+pub fn validate(landscape: &[Vec<(f64,f64)>], tolerance: Tolerance) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (i, level) in landscape.iter().enumerate() {
+        check_level(level, i, tolerance, &mut violations);
+    }
+    for (i, pair) in landscape.windows(2).enumerate() {
+        let xs = piecewiselinear::breakpoints(&[&pair[0], &pair[1]]);
+        for x in xs.into_iter().filter(|x| x.is_finite()) {
+            let value = piecewiselinear::evaluate(&pair[0], x);
+            let next_value = piecewiselinear::evaluate(&pair[1], x);
+            if value < next_value && !tolerance.approx_eq(value, next_value) {
+                violations.push(Violation::NotOrdered { level: i, x, value, next_value });
+            }
+        }
+    }
+    violations
+}
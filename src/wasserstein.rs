@@ -0,0 +1,257 @@
+#![warn(
+     clippy::all,
+     clippy::pedantic,
+     clippy::nursery,
+     clippy::cargo,
+ )]
+
+use std::collections::VecDeque;
+
+use crate::birthdeath::BirthDeath;
+use crate::bottleneck::{self, Matched, Matching};
+use crate::error::Error;
+
+/// Largest combined diagram size solved exactly by `wasserstein`
+pub const EXACT_LIMIT: usize = 256;
+
+/// Relative error used by `wasserstein` for diagrams above `EXACT_LIMIT`
+pub const DEFAULT_RELATIVE_ERROR: f64 = 0.01;
+
+// Square assignment problem between the first diagram plus the diagonal projections of the
+// second (rows) and the second diagram plus the projections of the first (columns). Any
+// projection can absorb any point at its diagonal cost and projections match each other for free.
+struct CostMatrix<'a> {
+    a: Vec<&'a BirthDeath>,
+    b: Vec<&'a BirthDeath>,
+    q: f64,
+}
+
+impl CostMatrix<'_> {
+    fn size(&self) -> usize {
+        self.a.len() + self.b.len()
+    }
+
+    fn cost(&self, row: usize, col: usize) -> f64 {
+        let (n, m) = (self.a.len(), self.b.len());
+        match (row < n, col < m) {
+            (true, true) => bottleneck::point_distance(self.a[row], self.b[col]).powf(self.q),
+            (true, false) => bottleneck::diagonal_distance(self.a[row]).powf(self.q),
+            (false, true) => bottleneck::diagonal_distance(self.b[row - n]).powf(self.q),
+            (false, false) => 0.0,
+        }
+    }
+}
+
+// Hungarian algorithm with potentials, O(n^3). Returns the column assigned to each row.
+fn hungarian(costs: &CostMatrix) -> Vec<usize> {
+    let size = costs.size();
+    // 1 indexed, column 0 is a virtual start
+    let mut u = vec![0.0; size + 1];
+    let mut v = vec![0.0; size + 1];
+    let mut row_of = vec![0; size + 1];
+    let mut way = vec![0; size + 1];
+    for row in 1..=size {
+        row_of[0] = row;
+        let mut col0 = 0;
+        let mut min_v = vec![f64::INFINITY; size + 1];
+        let mut used = vec![false; size + 1];
+        loop {
+            used[col0] = true;
+            let row0 = row_of[col0];
+            let mut delta = f64::INFINITY;
+            let mut col1 = 0;
+            for col in 1..=size {
+                if used[col] {
+                    continue;
+                }
+                let reduced = costs.cost(row0 - 1, col - 1) - u[row0] - v[col];
+                if reduced < min_v[col] {
+                    min_v[col] = reduced;
+                    way[col] = col0;
+                }
+                if min_v[col] < delta {
+                    delta = min_v[col];
+                    col1 = col;
+                }
+            }
+            for col in 0..=size {
+                if used[col] {
+                    u[row_of[col]] += delta;
+                    v[col] -= delta;
+                } else {
+                    min_v[col] -= delta;
+                }
+            }
+            col0 = col1;
+            if row_of[col0] == 0 {
+                break;
+            }
+        }
+        // Flip the augmenting path
+        while col0 != 0 {
+            let col1 = way[col0];
+            row_of[col0] = row_of[col1];
+            col0 = col1;
+        }
+    }
+    let mut assignment = vec![0; size];
+    for col in 1..=size {
+        assignment[row_of[col] - 1] = col - 1;
+    }
+    assignment
+}
+
+// Forward auction with epsilon scaling. Every phase ends with an epsilon optimal assignment whose
+// cost is compared with the dual lower bound given by the prices; it stops once the q-th root of
+// the cost is within `relative_error` of the q-th root of the bound. Every bid scans all columns.
+fn auction(costs: &CostMatrix, relative_error: f64) -> Result<Vec<usize>, Error> {
+    let size = costs.size();
+    let max_cost = (0..size)
+        .flat_map(|i| (0..size).map(move |j| (i, j)))
+        .map(|(i, j)| costs.cost(i, j))
+        .fold(0.0, f64::max);
+    if max_cost <= 0.0 {
+        return Ok((0..size).collect());
+    }
+    let target = (1.0 + relative_error).powf(costs.q);
+    let min_epsilon = max_cost * f64::EPSILON;
+    let mut prices = vec![0.0; size];
+    let mut epsilon = max_cost / 4.0;
+    loop {
+        let mut owner: Vec<Option<usize>> = vec![None; size];
+        let mut assignment: Vec<Option<usize>> = vec![None; size];
+        let mut unassigned: VecDeque<usize> = (0..size).collect();
+        while let Some(row) = unassigned.pop_front() {
+            let (mut best, mut best_value, mut second_value) = (0, f64::NEG_INFINITY, f64::NEG_INFINITY);
+            for (col, price) in prices.iter().enumerate() {
+                let value = -costs.cost(row, col) - price;
+                if value > best_value {
+                    second_value = best_value;
+                    best_value = value;
+                    best = col;
+                } else if value > second_value {
+                    second_value = value;
+                }
+            }
+            let increment = if second_value.is_finite() { best_value - second_value } else { 0.0 };
+            prices[best] += increment + epsilon;
+            if let Some(previous) = owner[best].replace(row) {
+                assignment[previous] = None;
+                unassigned.push_back(previous);
+            }
+            assignment[row] = Some(best);
+        }
+        let assignment: Vec<usize> = assignment.into_iter().map(Option::unwrap_or_default).collect();
+
+        let primal: f64 = assignment.iter().enumerate().map(|(row, &col)| costs.cost(row, col)).sum();
+        let dual: f64 = (0..size)
+            .map(|row| {
+                prices
+                    .iter()
+                    .enumerate()
+                    .map(|(col, price)| costs.cost(row, col) + price)
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum::<f64>()
+            - prices.iter().sum::<f64>();
+        if primal <= 0.0 || primal <= target * dual {
+            return Ok(assignment);
+        }
+        // Smaller steps are lost to rounding in the prices
+        if epsilon <= min_epsilon {
+            return Err(Error::NotConverged {
+                requested: relative_error,
+                achieved: (primal / dual).powf(1.0 / costs.q) - 1.0,
+            });
+        }
+        epsilon /= 5.0;
+    }
+}
+
+fn solve(a: &[BirthDeath], b: &[BirthDeath], q: f64, relative_error: Option<f64>) -> Result<Matching, Error> {
+    if q.is_nan() || q < 1.0 {
+        return Err(Error::InvalidArgument(format!("Wasserstein distance requires q >= 1, got {q}")));
+    }
+    let (fa, ea) = bottleneck::split(a);
+    let (fb, eb) = bottleneck::split(b);
+    let (essential_costs, mut pairs) = bottleneck::match_essential(a, &ea, b, &eb);
+
+    let costs = CostMatrix {
+        a: fa.iter().map(|&i| &a[i]).collect(),
+        b: fb.iter().map(|&j| &b[j]).collect(),
+        q,
+    };
+    let assignment = match relative_error {
+        Some(delta) => auction(&costs, delta)?,
+        None => hungarian(&costs),
+    };
+    let mut total: f64 = essential_costs.iter().map(|c| c.powf(q)).sum();
+    for (row, col) in assignment.into_iter().enumerate() {
+        total += costs.cost(row, col);
+        match (row < fa.len(), col < fb.len()) {
+            (true, true) => pairs.push((Matched::Pair(fa[row]), Matched::Pair(fb[col]))),
+            (true, false) => pairs.push((Matched::Pair(fa[row]), Matched::Diagonal)),
+            (false, true) => pairs.push((Matched::Diagonal, Matched::Pair(fb[col]))),
+            (false, false) => {}
+        }
+    }
+    Ok(Matching {
+        distance: total.powf(1.0 / q),
+        pairs,
+    })
+}
+
+/// Exact q-Wasserstein distance between two diagrams under the L-infinity ground metric
+///
+/// Points are matched to each other or to their projection on the diagonal by the Hungarian
+/// algorithm. Pairs that never die are matched among themselves in order of birth.
+///
+/// # Errors
+///
+/// Will return 'Err' if `q` is less than 1
+pub fn wasserstein_exact(a: &[BirthDeath], b: &[BirthDeath], q: f64) -> Result<Matching, Error> {
+    solve(a, b, q, None)
+}
+
+/// Approximate q-Wasserstein distance within a factor of 1 + `relative_error` of the exact one
+///
+/// Uses the auction algorithm with epsilon scaling. Every bid scans all n + m candidates, so a
+/// round of bids costs O((n + m)^2) and the worst case is no better than the O((n + m)^3) of
+/// `wasserstein_exact`. In practice few rounds are needed for a loose `relative_error`.
+///
+/// # Errors
+///
+/// Will return 'Err' if `q` is less than 1, `relative_error` is not positive, or the prices stop
+/// resolving smaller steps before the requested error is reached, in which case the error holds
+/// the relative error that was reached
+pub fn wasserstein_auction(a: &[BirthDeath], b: &[BirthDeath], q: f64, relative_error: f64) -> Result<Matching, Error> {
+    if relative_error.is_nan() || relative_error <= 0.0 {
+        return Err(Error::InvalidArgument("Relative error must be positive".to_string()));
+    }
+    solve(a, b, q, Some(relative_error))
+}
+
+/// q-Wasserstein distance, exact up to `EXACT_LIMIT` points and within `DEFAULT_RELATIVE_ERROR`
+/// above it
+///
+/// # Errors
+///
+/// Will return 'Err' if `q` is less than 1 or the auction does not reach
+/// `DEFAULT_RELATIVE_ERROR`
+pub fn wasserstein(a: &[BirthDeath], b: &[BirthDeath], q: f64) -> Result<Matching, Error> {
+    if a.len() + b.len() <= EXACT_LIMIT {
+        wasserstein_exact(a, b, q)
+    } else {
+        wasserstein_auction(a, b, q, DEFAULT_RELATIVE_ERROR)
+    }
+}
+
+/// q-Wasserstein distance between two diagrams, see `wasserstein`
+///
+/// # Errors
+///
+/// Will return 'Err' if `q` is less than 1 or the auction does not reach
+/// `DEFAULT_RELATIVE_ERROR`
+pub fn wasserstein_distance(a: &[BirthDeath], b: &[BirthDeath], q: f64) -> Result<f64, Error> {
+    Ok(wasserstein(a, b, q)?.distance)
+}